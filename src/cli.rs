@@ -0,0 +1,282 @@
+use crate::prelude::*;
+
+const USAGE: &str = "\
+Usage: dungeon_crawler [COMMAND]
+
+Without a command, the game starts as usual.
+
+Commands:
+  dump [--seed N] [--level N] [--architect NAME] [--theme NAME] [--html FILE]
+      Generate a level and print it as ASCII, or save it as colored HTML.
+      Architects: rooms, drunkard, automata, empty. Themes: dungeon, forest.
+  export-tiled <FILE> [--seed N] [--level N] [--architect NAME]
+      Generate a level and save it as a Tiled JSON map
+  bench-fov [--seed N] [--iterations N] [--monsters N] [--architect NAME]
+      Time field-of-view storage for the player and a number of monsters (default 50)";
+
+/// Runs a headless command from the command line, without opening a window
+pub fn run(args: &[String]) {
+    match args[0].as_str() {
+        "dump" => dump(&args[1..]),
+        "export-tiled" => export_tiled(&args[1..]),
+        "bench-fov" => bench_fov(&args[1..]),
+        _ => println!("{}", USAGE),
+    }
+}
+
+fn export_tiled(args: &[String]) {
+    let Some(path) = args.first() else {
+        println!("{}", USAGE);
+        return;
+    };
+
+    let mb = build_level(&args[1..]);
+    TiledMap::from_builder(&mb).save(path);
+    println!("Saved level to {}", path);
+}
+
+/// Compares the old hash set visibility storage with `VisibleTiles`
+///
+/// Every iteration does what a turn does to each field of view: rebuild it, clone it
+/// as `clone_dirty` does after a move, and probe every map cell as `map_render` does.
+fn bench_fov(args: &[String]) {
+    use std::collections::HashSet;
+
+    let mb = build_level(args);
+    let iterations: u32 = option(args, "--iterations")
+        .map(|n| n.parse().expect("Invalid iteration count"))
+        .unwrap_or(100);
+    let monsters: usize = option(args, "--monsters")
+        .map(|n| n.parse().expect("Invalid monster count"))
+        .unwrap_or(50);
+
+    // Spread the monsters evenly over the floor, so the benchmark does not depend on
+    // how many spawn points the architect happened to pick
+    let floor: Vec<Point> = mb
+        .map
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tile)| **tile == TileType::Floor)
+        .map(|(idx, _)| mb.map.index_to_point2d(idx))
+        .collect();
+    let mut viewers = vec![(mb.player_start, 8)];
+    viewers.extend((0..monsters).map(|i| (floor[i * floor.len() / monsters.max(1)], 6)));
+    println!(
+        "{} fields of view, {} iterations",
+        viewers.len(),
+        iterations
+    );
+
+    bench_storage(
+        "HashSet<Point>",
+        &viewers,
+        iterations,
+        |pos, radius| field_of_view_set(pos, radius, &mb.map),
+        |tiles: &HashSet<Point>, pos| tiles.contains(pos),
+    );
+    bench_storage(
+        "VisibleTiles",
+        &viewers,
+        iterations,
+        |pos, radius| field_of_view(pos, radius, &mb.map).into_iter().collect(),
+        |tiles: &VisibleTiles, pos| tiles.contains(pos),
+    );
+}
+
+fn bench_storage<T: Clone>(
+    name: &str,
+    viewers: &[(Point, i32)],
+    iterations: u32,
+    build: impl Fn(Point, i32) -> T,
+    contains: impl Fn(&T, &Point) -> bool,
+) {
+    use std::hint::black_box;
+    use std::time::{Duration, Instant};
+
+    let (mut build_time, mut clone_time, mut probe_time) =
+        (Duration::ZERO, Duration::ZERO, Duration::ZERO);
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let fovs: Vec<T> = viewers
+            .iter()
+            .map(|(pos, radius)| build(*pos, *radius))
+            .collect();
+        build_time += start.elapsed();
+
+        let start = Instant::now();
+        black_box(fovs.clone());
+        clone_time += start.elapsed();
+
+        let start = Instant::now();
+        let mut visible = 0;
+        for fov in fovs.iter() {
+            for y in 0..SCREEN_HEIGHT {
+                for x in 0..SCREEN_WIDTH {
+                    if contains(fov, &Point::new(x, y)) {
+                        visible += 1;
+                    }
+                }
+            }
+        }
+        black_box(visible);
+        probe_time += start.elapsed();
+    }
+
+    let per_turn = |total: Duration| total / iterations.max(1);
+    println!(
+        "{:<16} build {:>10.2?}  clone {:>10.2?}  probe {:>10.2?}  per turn",
+        name,
+        per_turn(build_time),
+        per_turn(clone_time),
+        per_turn(probe_time)
+    );
+}
+
+/// Generates the level described by the `--seed`, `--level` and `--architect` options
+fn build_level(args: &[String]) -> MapBuilder {
+    let mut rng = match option(args, "--seed") {
+        Some(seed) => RandomNumberGenerator::seeded(seed.parse().expect("Invalid seed")),
+        None => RandomNumberGenerator::new(),
+    };
+    let map_level = option(args, "--level")
+        .map(|level| level.parse().expect("Invalid level"))
+        .unwrap_or(0);
+
+    let mut mb = match option(args, "--architect") {
+        Some(name) => {
+            let architect = ArchitectChoice::from_name(name).expect("Unknown architect");
+            MapBuilder::with_architect(&mut rng, architect, &LevelConfig::load())
+        }
+        None => MapBuilder::new(&mut rng, map_level),
+    };
+
+    if let Some(name) = option(args, "--theme") {
+        mb.theme = ThemeChoice::from_name(name).expect("Unknown theme").build();
+    }
+
+    // Place the exit the same way the game does
+    if map_level != AMULET_LEVEL {
+        let exit_idx = mb.map.point2d_to_index(mb.amulet_start);
+        mb.map.tiles[exit_idx] = TileType::Exit;
+    }
+
+    mb
+}
+
+fn dump(args: &[String]) {
+    let mb = build_level(args);
+    let rows = level_glyphs(&mb);
+
+    match option(args, "--html") {
+        Some(path) => {
+            std::fs::write(path, to_html(&rows)).expect("Failed to write HTML file");
+            println!("Saved level to {}", path);
+        }
+        None => rows.iter().for_each(|row| {
+            println!("{}", row.iter().map(|(ch, _)| *ch).collect::<String>());
+        }),
+    }
+}
+
+/// The character and color of every map cell, using the level's theme glyphs
+///
+/// The player start, amulet, spawns and decorations are drawn over the tiles.
+fn level_glyphs(mb: &MapBuilder) -> Vec<Vec<(char, RGB)>> {
+    let mut rows: Vec<Vec<(char, RGB)>> = (0..SCREEN_HEIGHT)
+        .map(|y| {
+            (0..SCREEN_WIDTH)
+                .map(|x| {
+                    let tile = mb.map.tiles[map_idx(x, y)];
+                    let color = match tile {
+                        TileType::Wall => RGB::named(GRAY),
+                        TileType::Floor => RGB::named(DARK_GRAY),
+                        TileType::Exit => RGB::named(CYAN),
+                    };
+                    (to_char(mb.theme.tile_to_render(tile) as u8), color)
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut mark = |pos: Point, ch: char, color: RGB| {
+        rows[pos.y as usize][pos.x as usize] = (ch, color);
+    };
+
+    mb.decorations.iter().for_each(|(pos, render)| {
+        mark(*pos, to_char(render.glyph as u8), render.color.fg.to_rgb());
+    });
+    mb.monster_spawns
+        .iter()
+        .for_each(|pos| mark(*pos, 'M', RGB::named(RED)));
+
+    let templates = Templates::load();
+    mb.named_spawns.iter().for_each(|(pos, name)| {
+        let glyph = templates
+            .entities
+            .iter()
+            .find(|t| t.name == *name)
+            .map(|t| t.glyph)
+            .unwrap_or('?');
+        mark(*pos, glyph, RGB::named(ORANGE));
+    });
+
+    if mb.map.tiles[mb.map.point2d_to_index(mb.amulet_start)] != TileType::Exit {
+        mark(mb.amulet_start, '|', RGB::named(GOLD));
+    }
+    mark(mb.player_start, '@', RGB::named(YELLOW));
+
+    rows
+}
+
+/// A standalone HTML page showing the level in color
+fn to_html(rows: &[Vec<(char, RGB)>]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Dungeon Crawler level</title>
+</head>
+<body style=\"background: black\">
+<pre style=\"font-family: monospace; line-height: 1\">
+",
+    );
+
+    for row in rows.iter() {
+        for (ch, color) in row.iter() {
+            let escaped = match ch {
+                '<' => "&lt;".to_string(),
+                '>' => "&gt;".to_string(),
+                '&' => "&amp;".to_string(),
+                '"' => "&quot;".to_string(),
+                _ => ch.to_string(),
+            };
+            html.push_str(&format!(
+                "<span style=\"color: #{:02x}{:02x}{:02x}\">{}</span>",
+                (color.r * 255.0) as u8,
+                (color.g * 255.0) as u8,
+                (color.b * 255.0) as u8,
+                escaped
+            ));
+        }
+        html.push('\n');
+    }
+
+    html.push_str(
+        "</pre>
+</body>
+</html>
+",
+    );
+    html
+}
+
+/// The value following a `--name` option, if present
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|idx| args.get(idx + 1))
+        .map(|value| value.as_str())
+}
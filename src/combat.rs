@@ -0,0 +1,293 @@
+use crate::prelude::*;
+
+/// A d20 roll plus the attacker's accuracy must beat this plus the defender's defense
+const TO_HIT_TARGET: i32 = 5;
+
+/// Everything that decides how well an entity attacks, including the gear it carries
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AttackStats {
+    pub accuracy: i32,
+    pub damage: Damage,
+}
+
+/// Spells hit on anything but a natural 1
+const SPELL_ACCURACY: i32 = 100;
+
+/// Everything that decides how well an entity avoids and soaks up attacks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DefenseStats {
+    pub defense: i32,
+    pub armor: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttackOutcome {
+    Miss,
+    /// The attack landed. `damage` is what is left after armor, and may be zero.
+    Hit {
+        damage: i32,
+    },
+}
+
+impl AttackStats {
+    /// The attacker's own melee stats plus those of the items it has equipped
+    pub fn of(ecs: &impl EntityStore, attacker: Entity) -> Self {
+        let mut stats = Self {
+            accuracy: 0,
+            damage: Damage { min: 0, max: 0 },
+        };
+        if let Ok(entry) = ecs.entry_ref(attacker) {
+            if let Ok(accuracy) = entry.get_component::<Accuracy>() {
+                stats.accuracy += accuracy.0;
+            }
+            if let Ok(damage) = entry.get_component::<Damage>() {
+                stats.damage = *damage;
+            }
+        }
+
+        // Ranged weapons, like bows, are no help in melee
+        <(&Equipped, Option<&Damage>, Option<&Accuracy>)>::query()
+            .filter(!component::<Ranged>())
+            .iter(ecs)
+            .filter(|(equipped, ..)| equipped.owner == attacker)
+            .for_each(|(_, damage, accuracy)| {
+                if let Some(damage) = damage {
+                    stats.damage.min += damage.min;
+                    stats.damage.max += damage.max;
+                }
+                stats.accuracy += accuracy.map_or(0, |accuracy| accuracy.0);
+            });
+
+        stats.hunger_penalty(ecs, attacker)
+    }
+}
+
+impl AttackStats {
+    /// A spell, like a fireball, that does a fixed amount of damage and nearly always hits
+    pub fn spell(damage: i32) -> Self {
+        Self {
+            accuracy: SPELL_ACCURACY,
+            damage: Damage {
+                min: damage,
+                max: damage,
+            },
+        }
+    }
+
+    /// Attacking with a specific item, like a bow or a thrown dagger, instead of the
+    /// attacker's melee gear. The attacker's own accuracy still counts.
+    pub fn with_weapon(ecs: &impl EntityStore, attacker: Entity, weapon: Entity) -> Self {
+        let mut stats = Self {
+            accuracy: 0,
+            damage: Damage { min: 0, max: 0 },
+        };
+        if let Ok(entry) = ecs.entry_ref(attacker) {
+            if let Ok(accuracy) = entry.get_component::<Accuracy>() {
+                stats.accuracy += accuracy.0;
+            }
+        }
+        if let Ok(entry) = ecs.entry_ref(weapon) {
+            if let Ok(accuracy) = entry.get_component::<Accuracy>() {
+                stats.accuracy += accuracy.0;
+            }
+            if let Ok(damage) = entry.get_component::<Damage>() {
+                stats.damage = *damage;
+            }
+        }
+
+        stats.hunger_penalty(ecs, attacker)
+    }
+
+    /// Hungry attackers hit less often, and weak ones hit softer as well
+    fn hunger_penalty(mut self, ecs: &impl EntityStore, attacker: Entity) -> Self {
+        let Some(state) = ecs
+            .entry_ref(attacker)
+            .ok()
+            .and_then(|entry| entry.get_component::<Hunger>().ok().map(Hunger::state))
+        else {
+            return self;
+        };
+
+        self.accuracy -= state.accuracy_penalty();
+        self.damage.min = i32::max(0, self.damage.min - state.damage_penalty());
+        self.damage.max = i32::max(0, self.damage.max - state.damage_penalty());
+        self
+    }
+}
+
+impl DefenseStats {
+    /// The defender's own stats plus those of the items it has equipped
+    pub fn of(ecs: &impl EntityStore, defender: Entity) -> Self {
+        let mut stats = Self {
+            defense: 0,
+            armor: 0,
+        };
+        if let Ok(entry) = ecs.entry_ref(defender) {
+            if let Ok(defense) = entry.get_component::<Defense>() {
+                stats.defense += defense.0;
+            }
+            if let Ok(armor) = entry.get_component::<Armor>() {
+                stats.armor += armor.0;
+            }
+        }
+
+        <(&Equipped, Option<&Defense>, Option<&Armor>)>::query()
+            .iter(ecs)
+            .filter(|(equipped, ..)| equipped.owner == defender)
+            .for_each(|(_, defense, armor)| {
+                stats.defense += defense.map_or(0, |defense| defense.0);
+                stats.armor += armor.map_or(0, |armor| armor.0);
+            });
+
+        stats
+    }
+}
+
+/// Roll to hit, then roll damage and let armor absorb what it can
+///
+/// A natural 20 always hits and a natural 1 always misses, so no fight is ever
+/// certain. This is shared by everything that hurts, so melee, ranged attacks and
+/// traps all follow the same rules.
+pub fn resolve_attack(
+    rng: &mut RandomNumberGenerator,
+    attack: &AttackStats,
+    defense: &DefenseStats,
+) -> AttackOutcome {
+    let roll = rng.roll_dice(1, 20);
+    let hits = match roll {
+        20 => true,
+        1 => false,
+        _ => roll + attack.accuracy > TO_HIT_TARGET + defense.defense,
+    };
+    if !hits {
+        return AttackOutcome::Miss;
+    }
+
+    let min = attack.damage.min.max(0);
+    let max = attack.damage.max.max(min);
+    let damage = rng.range(min, max + 1);
+
+    AttackOutcome::Hit {
+        damage: (damage - defense.armor).max(0),
+    }
+}
+
+/// Removes a monster that has died. Whoever killed it earns its experience, and
+/// whatever gold and items it had fall to the floor where it died.
+///
+/// Everything that kills goes through here, so every death is rewarded the same.
+pub fn kill(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    victim: Entity,
+    killer: Option<Entity>,
+) {
+    let Ok(entry) = ecs.entry_ref(victim) else {
+        return;
+    };
+    let xp_value = entry.get_component::<XpValue>().map_or(0, |xp| xp.0);
+    let gold = entry.get_component::<Gold>().map_or(0, |gold| gold.0);
+    let pos = entry.get_component::<Point>().ok().copied();
+    commands.remove(victim);
+
+    <(Entity, &Carried)>::query()
+        .iter(ecs)
+        .filter(|(_, carried)| carried.0 == victim)
+        .for_each(|(item, _)| match pos {
+            Some(pos) => {
+                commands.remove_component::<Carried>(*item);
+                commands.add_component(*item, pos);
+            }
+            None => commands.remove(*item),
+        });
+
+    if let Some(Ok(mut killer)) = killer.map(|killer| ecs.entry_mut(killer)) {
+        if let Ok(experience) = killer.get_component_mut::<Experience>() {
+            experience.xp += xp_value;
+        }
+    }
+    if let (true, Some(pos)) = (gold > 0, pos) {
+        spawn_gold(commands, pos, gold);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIALS: usize = 1000;
+
+    fn attack(accuracy: i32, min: i32, max: i32) -> AttackStats {
+        AttackStats {
+            accuracy,
+            damage: Damage { min, max },
+        }
+    }
+
+    fn defense(defense: i32, armor: i32) -> DefenseStats {
+        DefenseStats { defense, armor }
+    }
+
+    /// Resolves many attacks, along with the d20 each one rolled. A second generator
+    /// with the same seed replays the rolls that `resolve_attack` makes.
+    fn outcomes(attack: AttackStats, defense: DefenseStats) -> Vec<(i32, AttackOutcome)> {
+        let mut rng = RandomNumberGenerator::seeded(42);
+        let mut replay = RandomNumberGenerator::seeded(42);
+        (0..TRIALS)
+            .map(|_| {
+                let outcome = resolve_attack(&mut rng, &attack, &defense);
+                let roll = replay.roll_dice(1, 20);
+                if let AttackOutcome::Hit { .. } = outcome {
+                    replay.range(attack.damage.min, attack.damage.max + 1);
+                }
+                (roll, outcome)
+            })
+            .collect()
+    }
+
+    fn damage_dealt(outcomes: &[(i32, AttackOutcome)]) -> Vec<i32> {
+        outcomes
+            .iter()
+            .filter_map(|(_, outcome)| match outcome {
+                AttackOutcome::Hit { damage } => Some(*damage),
+                AttackOutcome::Miss => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hopeless_attacks_only_hit_on_a_natural_20() {
+        let outcomes = outcomes(attack(-100, 1, 1), defense(100, 0));
+        outcomes.iter().for_each(|(roll, outcome)| {
+            assert_eq!(*roll == 20, matches!(outcome, AttackOutcome::Hit { .. }));
+        });
+        assert!(outcomes.iter().any(|(roll, _)| *roll == 20));
+    }
+
+    #[test]
+    fn certain_attacks_only_miss_on_a_natural_1() {
+        let outcomes = outcomes(attack(100, 1, 1), defense(-100, 0));
+        outcomes.iter().for_each(|(roll, outcome)| {
+            assert_eq!(*roll == 1, *outcome == AttackOutcome::Miss);
+        });
+        assert!(outcomes.iter().any(|(roll, _)| *roll == 1));
+    }
+
+    #[test]
+    fn armor_absorbs_damage_but_never_below_zero() {
+        let soaked = damage_dealt(&outcomes(attack(100, 2, 4), defense(0, 1)));
+        assert!(!soaked.is_empty());
+        assert!(soaked.iter().all(|damage| (1..=3).contains(damage)));
+
+        let blocked = damage_dealt(&outcomes(attack(100, 1, 3), defense(0, 10)));
+        assert!(!blocked.is_empty());
+        assert!(blocked.iter().all(|damage| *damage == 0));
+    }
+
+    #[test]
+    fn damage_stays_within_its_range() {
+        let dealt = damage_dealt(&outcomes(attack(100, 2, 5), defense(0, 0)));
+        assert!(dealt.iter().all(|damage| (2..=5).contains(damage)));
+        (2..=5).for_each(|damage| assert!(dealt.contains(&damage)));
+    }
+}
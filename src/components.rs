@@ -1,5 +1,5 @@
 pub use crate::prelude::*;
-use std::collections::HashSet;
+use serde::Deserialize;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Render {
@@ -15,8 +15,59 @@ pub struct Player {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Enemy;
 
+/// The side an entity is on. See `FactionTable` for how factions treat each other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Faction(pub String);
+
+/// The player's level, and the experience earned so far over the whole game
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+}
+
+/// How much the player has eaten. It goes down every turn, see `HungerState`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hunger {
+    pub satiety: i32,
+}
+
+/// How quickly an entity gets to act, see `ENERGY_TO_ACT`
+///
+/// Every turn the entity gains its speed in energy, and every action it takes costs some.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Energy {
+    pub speed: i32,
+    pub current: i32,
+}
+
+/// Heals a point of health every this many turns
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Regeneration(pub i32);
+
+/// Component for a player waiting until they are healed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resting {
+    /// Health after the last wait, so that losing any interrupts the rest
+    pub health: i32,
+}
+
+/// Experience awarded for killing the entity
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct MovingRandomly;
+pub struct XpValue(pub i32);
+
+/// Gold an entity has: the player's purse, what a monster drops when it is killed, or
+/// the amount in a pile on the floor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gold(pub i32);
+
+/// Component for a monster that sells items, which it carries, and buys the player's
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shopkeeper;
+
+/// Component for entities that no other blocking entity may share a tile with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlocksTile;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToMove {
@@ -24,6 +75,9 @@ pub struct WantsToMove {
     pub entity: Entity,
     /// The destination to move to
     pub destination: Point,
+    /// Whether the entity is teleported there rather than walking, which
+    /// confusion cannot throw off and which makes no footstep noise
+    pub teleport: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -35,18 +89,79 @@ pub struct Health {
 #[derive(Clone, PartialEq)]
 pub struct Name(pub String);
 
+/// What the inventory screen says about an item
+#[derive(Clone, PartialEq)]
+pub struct Description(pub String);
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToAttack {
     pub attacker: Entity,
     pub victim: Entity,
+    /// Stats to attack with instead of the attacker's own, e.g. those of a thrown dagger
+    pub stats: Option<AttackStats>,
+}
+
+/// How a monster behaves, chosen by the `ai` field of its template
+///
+/// Every behavior goes after the nearest hostile entity it can see, but differs in
+/// when it gives chase and what it does in the meantime.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Ai {
+    /// Wanders about at random until it sees a hostile, then chases it
+    Wander,
+    /// Chases any hostile it sees, and stays put until then. Once it loses its quarry
+    /// and gives up the search, it wanders instead.
+    Chase,
+    /// Wanders and chases, but runs away once its health is at or below
+    /// this percentage of its maximum
+    Coward { flee_below: i32 },
+    /// Stays this many tiles away from its target and shoots at it
+    Skirmish { distance: i32 },
+    /// Stays at its `GuardPost`, and only chases hostiles within this radius of it
+    Guard { radius: i32 },
+    /// Lies in wait until a hostile comes within this radius, then chases it for good
+    Ambush { radius: i32 },
+}
+
+/// Where a monster last saw a hostile it has lost sight of
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LastSeen {
+    pub pos: Point,
+    /// Turns left to look around `pos` before giving up, which only count down once
+    /// the monster is there
+    pub patience: i32,
+    /// Whether the monster has reached `pos` and is looking around it
+    pub arrived: bool,
+}
+
+/// Represents a noise made on a tile, which monsters nearby may hear
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Noise {
+    /// Whoever made the noise, so that its friends can ignore it
+    pub source: Entity,
+    pub pos: Point,
+    pub volume: i32,
 }
 
+/// Makes the wearer's steps louder, or quieter if negative
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct ChasingPlayer;
+pub struct NoiseModifier(pub i32);
+
+/// Component for a player in sneaking mode, whose steps are quieter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sneaking;
+
+/// The tile a guarding monster returns to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GuardPost(pub Point);
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Item;
 
+/// What an item costs in a shop. Shopkeepers buy it for half as much.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Value(pub i32);
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct AmuletOfYendor;
 
@@ -56,9 +171,14 @@ pub struct AmuletOfYendor;
 /// In roguelike games, the Field of View (FOV) represents the area that an entity can see
 /// based on its position and surroundings. This component stores the visible tiles, the radius
 /// of visibility, and a dirty flag to indicate if the FOV needs to be recalculated.
+///
+/// Entities with `Darkvision` only see the tiles in their line of sight that are lit,
+/// or close enough to see in the dark. Everyone else sees their whole line of sight.
 #[derive(Clone, Debug, PartialEq)]
 pub struct FieldOfView {
-    pub visible_tiles: HashSet<Point>,
+    pub visible_tiles: VisibleTiles,
+    /// Every tile within the radius that is not hidden behind a wall, lit or not
+    pub line_of_sight: VisibleTiles,
     pub radius: i32,
     pub is_dirty: bool,
 }
@@ -66,7 +186,8 @@ pub struct FieldOfView {
 impl FieldOfView {
     pub fn new(radius: i32) -> Self {
         Self {
-            visible_tiles: HashSet::new(),
+            visible_tiles: VisibleTiles::new(),
+            line_of_sight: VisibleTiles::new(),
             radius,
             is_dirty: true,
         }
@@ -75,6 +196,7 @@ impl FieldOfView {
     pub fn clone_dirty(&self) -> Self {
         Self {
             visible_tiles: self.visible_tiles.clone(),
+            line_of_sight: self.line_of_sight.clone(),
             radius: self.radius,
             is_dirty: true,
         }
@@ -89,6 +211,43 @@ pub struct ProvidesHealing {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ProvidesDungeonMap;
 
+/// Food, which fills the user up by this much
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesNutrition(pub i32);
+
+/// Damages everything within a radius of where the item lands, like a fireball
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesAreaDamage {
+    pub damage: i32,
+    pub radius: i32,
+}
+
+/// Strikes the target, then arcs on to the nearest hostiles that have not been struck yet
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesChainLightning {
+    pub damage: i32,
+    /// How many more enemies the lightning arcs to after the first
+    pub jumps: i32,
+}
+
+/// Damages the target, which it rarely misses
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesMagicMissile {
+    pub damage: i32,
+}
+
+/// Wins the target over to the user's faction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesCharm;
+
+/// Identifies every item the user carries
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesIdentify;
+
+/// Moves the user to a random floor tile they could have walked to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesTeleport;
+
 /// Component that indicates an entity is being carried by another entity
 #[derive(Clone, PartialEq)]
 pub struct Carried(pub Entity);
@@ -98,10 +257,208 @@ pub struct Carried(pub Entity);
 pub struct ActivateItem {
     pub used_by: Entity,
     pub item: Entity,
+    /// Where a targeted item, like a scroll of fireball, landed
+    pub target: Option<Point>,
 }
 
+/// The range of damage an attack does, before armor
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Damage(pub i32);
+pub struct Damage {
+    pub min: i32,
+    pub max: i32,
+}
+
+/// Bonus to the to-hit roll of attacks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Accuracy(pub i32);
+
+/// Makes attacks against an entity less likely to hit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Defense(pub i32);
+
+/// Damage absorbed from every hit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Armor(pub i32);
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Weapon;
+
+/// Component for entities that light up the tiles around them, like torches or a lantern
+///
+/// Light reaches the tiles in the entity's field of view that are within `radius`,
+/// so a light source also needs a `FieldOfView`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightSource {
+    pub radius: i32,
+    pub color: RGB,
+}
+
+/// The distance at which an entity can see unlit tiles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Darkvision(pub i32);
+
+/// Where on the body an item is worn or held
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EquipmentSlot {
+    MainHand,
+    OffHand,
+    Body,
+    Head,
+    Ring,
+    Amulet,
+}
+
+impl EquipmentSlot {
+    pub fn name(self) -> &'static str {
+        match self {
+            EquipmentSlot::MainHand => "main hand",
+            EquipmentSlot::OffHand => "off hand",
+            EquipmentSlot::Body => "body",
+            EquipmentSlot::Head => "head",
+            EquipmentSlot::Ring => "ring",
+            EquipmentSlot::Amulet => "amulet",
+        }
+    }
+}
+
+/// Component for items that can be equipped in a slot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Equippable(pub EquipmentSlot);
+
+/// Component for carried items that are currently equipped.
+/// Only equipped items add their stats to their owner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Equipped {
+    pub owner: Entity,
+    pub slot: EquipmentSlot,
+}
+
+/// Represents the action of equipping a carried item, replacing whatever is in its slot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EquipItem {
+    pub owner: Entity,
+    pub item: Entity,
+}
+
+/// Represents the action of unequipping an item, which stays in the inventory
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnequipItem {
+    pub owner: Entity,
+    pub item: Entity,
+}
+
+/// Raises the maximum health of whoever has the item equipped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HealthBonus(pub i32);
+
+/// Raises the field of view radius of whoever has the item equipped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SightBonus(pub i32);
+
+/// The status effects that items and attacks can cause
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatusEffect {
+    Poison,
+    Confusion,
+    Sleep,
+    Haste,
+    Slow,
+}
+
+impl StatusEffect {
+    /// Parses the effect names used in templates
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Poison" => Some(Self::Poison),
+            "Confusion" => Some(Self::Confusion),
+            "Sleep" => Some(Self::Sleep),
+            "Haste" => Some(Self::Haste),
+            "Slow" => Some(Self::Slow),
+            _ => None,
+        }
+    }
+
+    /// Put the effect on an entity for a number of turns, replacing any running one
+    pub fn apply(self, commands: &mut CommandBuffer, entity: Entity, turns: i32, source: Entity) {
+        match self {
+            StatusEffect::Poison => commands.add_component(
+                entity,
+                Poisoned {
+                    turns,
+                    damage: POISON_DAMAGE,
+                    source,
+                },
+            ),
+            StatusEffect::Confusion => commands.add_component(entity, Confused { turns }),
+            StatusEffect::Sleep => commands.add_component(entity, Asleep { turns }),
+            StatusEffect::Haste => commands.add_component(entity, Hasted { turns }),
+            StatusEffect::Slow => commands.add_component(entity, Slowed { turns }),
+        }
+    }
+}
+
+const POISON_DAMAGE: i32 = 1;
+
+/// Loses `damage` health every turn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Poisoned {
+    pub turns: i32,
+    pub damage: i32,
+    /// Who caused the poisoning, and is credited if it kills
+    pub source: Entity,
+}
+
+/// Stumbles in a random direction instead of where it wants to go
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Confused {
+    pub turns: i32,
+}
+
+/// Does nothing until the effect wears off or something hits it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Asleep {
+    pub turns: i32,
+}
+
+/// Gains energy twice as fast
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hasted {
+    pub turns: i32,
+}
+
+/// Gains energy half as fast
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slowed {
+    pub turns: i32,
+}
+
+/// Component for items that put status effects on whoever uses them
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProvidesStatus(pub Vec<(StatusEffect, i32)>);
+
+/// Component for monsters whose hits put status effects on the victim
+#[derive(Clone, Debug, PartialEq)]
+pub struct InflictsStatus(pub Vec<(StatusEffect, i32)>);
+
+/// Component for items and monsters that attack from a distance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ranged {
+    pub range: i32,
+}
+
+/// Component for ranged items that fly at the target and land there, like throwing daggers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thrown;
+
+/// Number of uses an item has left, e.g. for wands
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Charges(pub i32);
+
+/// Represents the action of shooting or throwing at a tile
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToShoot {
+    pub shooter: Entity,
+    /// The item used, or `None` for monsters that shoot with their own attack
+    pub weapon: Option<Entity>,
+    pub target: Point,
+}
@@ -0,0 +1,124 @@
+use crate::prelude::*;
+
+/// Energy an entity needs before it can act
+pub const ENERGY_TO_ACT: i32 = 100;
+
+/// Energy a normal entity gains every turn, so it acts once per turn
+pub const NORMAL_SPEED: i32 = 100;
+
+/// What each kind of action costs. Anything else an entity does, including nothing,
+/// costs a full wait.
+pub const MOVE_COST: i32 = 100;
+pub const ATTACK_COST: i32 = 100;
+pub const ITEM_COST: i32 = 50;
+pub const WAIT_COST: i32 = 100;
+
+/// Counts the turns the energy scheduler lets pass
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Clock {
+    /// Turns that have passed since the game started
+    pub turn: i32,
+    /// Turns that have passed since status effects last counted down
+    pub pending_turns: i32,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many times an interval of turns came round in the turns still pending
+    pub fn pending_intervals(&self, interval: i32) -> i32 {
+        self.turn / interval - (self.turn - self.pending_turns) / interval
+    }
+}
+
+impl Energy {
+    /// A new entity is ready to act straight away
+    pub fn new(speed: i32) -> Self {
+        Self {
+            speed,
+            current: ENERGY_TO_ACT,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.current >= ENERGY_TO_ACT
+    }
+
+    /// The energy gained in a turn, after haste and slowness
+    pub fn gain(&self, hasted: bool, slowed: bool) -> i32 {
+        let mut speed = self.speed;
+        if hasted {
+            speed *= 2;
+        }
+        if slowed {
+            speed /= 2;
+        }
+        speed
+    }
+}
+
+/// Pay for an action out of an entity's energy
+pub fn spend_energy(
+    ecs: &impl EntityStore,
+    commands: &mut CommandBuffer,
+    entity: Entity,
+    cost: i32,
+) {
+    let energy = ecs
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Energy>().ok().copied());
+    if let Some(energy) = energy {
+        commands.add_component(
+            entity,
+            Energy {
+                current: energy.current - cost,
+                ..energy
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn haste_doubles_and_slowness_halves_the_gain() {
+        let energy = Energy::new(NORMAL_SPEED);
+        assert_eq!(energy.gain(false, false), NORMAL_SPEED);
+        assert_eq!(energy.gain(true, false), NORMAL_SPEED * 2);
+        assert_eq!(energy.gain(false, true), NORMAL_SPEED / 2);
+        assert_eq!(energy.gain(true, true), NORMAL_SPEED);
+    }
+
+    #[test]
+    fn new_entities_are_ready_to_act() {
+        let mut energy = Energy::new(50);
+        assert!(energy.is_ready());
+        energy.current -= 1;
+        assert!(!energy.is_ready());
+    }
+
+    #[test]
+    fn pending_intervals_counts_the_multiples_that_passed() {
+        let clock = Clock {
+            turn: 10,
+            pending_turns: 3,
+        };
+        // Turns 8, 9 and 10 are pending
+        assert_eq!(clock.pending_intervals(1), 3);
+        assert_eq!(clock.pending_intervals(2), 2);
+        assert_eq!(clock.pending_intervals(5), 1);
+        assert_eq!(clock.pending_intervals(4), 1);
+        assert_eq!(clock.pending_intervals(7), 0);
+
+        let clock = Clock {
+            turn: 10,
+            pending_turns: 0,
+        };
+        assert_eq!(clock.pending_intervals(1), 0);
+    }
+}
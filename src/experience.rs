@@ -0,0 +1,114 @@
+use crate::prelude::*;
+
+/// Maximum health every new level brings, on top of the chosen perk
+const HEALTH_PER_LEVEL: i32 = 5;
+
+/// The total experience needed to reach a level. Each level takes 20 more than the last.
+fn xp_for_level(level: i32) -> i32 {
+    10 * level * (level - 1)
+}
+
+impl Experience {
+    pub fn new() -> Self {
+        Self { level: 1, xp: 0 }
+    }
+
+    /// The total experience needed for the next level
+    pub fn next_level_xp(&self) -> i32 {
+        xp_for_level(self.level + 1)
+    }
+
+    pub fn can_level_up(&self) -> bool {
+        self.xp >= self.next_level_xp()
+    }
+}
+
+/// A bonus the player picks on reaching a new level
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Perk {
+    Toughness,
+    Strength,
+    Precision,
+    Agility,
+    KeenEyes,
+}
+
+impl Perk {
+    pub const ALL: [Perk; 5] = [
+        Perk::Toughness,
+        Perk::Strength,
+        Perk::Precision,
+        Perk::Agility,
+        Perk::KeenEyes,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Perk::Toughness => "Toughness",
+            Perk::Strength => "Strength",
+            Perk::Precision => "Precision",
+            Perk::Agility => "Agility",
+            Perk::KeenEyes => "Keen Eyes",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Perk::Toughness => "+10 maximum health",
+            Perk::Strength => "+1 damage",
+            Perk::Precision => "+1 accuracy",
+            Perk::Agility => "+1 defense",
+            Perk::KeenEyes => "+1 sight radius",
+        }
+    }
+}
+
+/// Raise the entity one level, granting the level's health and the chosen perk
+pub fn level_up(ecs: &mut World, entity: Entity, perk: Perk) {
+    let Some(mut entry) = ecs.entry(entity) else {
+        return;
+    };
+    let Ok(experience) = entry.get_component_mut::<Experience>() else {
+        return;
+    };
+    experience.level += 1;
+
+    let health_gain = HEALTH_PER_LEVEL
+        + match perk {
+            Perk::Toughness => 10,
+            _ => 0,
+        };
+    if let Ok(health) = entry.get_component_mut::<Health>() {
+        health.max += health_gain;
+        health.current += health_gain;
+    }
+
+    match perk {
+        Perk::Toughness => {}
+        Perk::Strength => {
+            let damage = entry
+                .get_component::<Damage>()
+                .copied()
+                .unwrap_or(Damage { min: 0, max: 0 });
+            entry.add_component(Damage {
+                min: damage.min + 1,
+                max: damage.max + 1,
+            });
+        }
+        Perk::Precision => {
+            let accuracy = entry.get_component::<Accuracy>().map_or(0, |a| a.0);
+            entry.add_component(Accuracy(accuracy + 1));
+        }
+        Perk::Agility => {
+            let defense = entry.get_component::<Defense>().map_or(0, |d| d.0);
+            entry.add_component(Defense(defense + 1));
+        }
+        Perk::KeenEyes => {
+            if let Ok(fov) = entry.get_component::<FieldOfView>() {
+                let mut fov = fov.clone_dirty();
+                fov.radius += 1;
+                entry.add_component(fov);
+            }
+        }
+    }
+}
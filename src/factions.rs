@@ -0,0 +1,106 @@
+use crate::prelude::*;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+
+/// How one faction treats another
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Reaction {
+    /// Attacked on sight
+    Hostile,
+    /// Left alone
+    Neutral,
+    /// Left alone, and never attacked by accident
+    Friendly,
+}
+
+/// How every faction reacts to the others, loaded from `resources/factions.ron`
+#[derive(Deserialize, Clone, Debug)]
+pub struct FactionTable {
+    /// The reaction between factions the table does not mention
+    pub default: Reaction,
+    /// For each faction, how it reacts to the factions listed
+    pub reactions: HashMap<String, HashMap<String, Reaction>>,
+}
+
+impl FactionTable {
+    pub fn load() -> Self {
+        let file = File::open("resources/factions.ron").expect("Failed to open factions file");
+
+        from_reader(file).expect("Failed to parse factions file")
+    }
+
+    /// How members of one faction react to members of another
+    ///
+    /// Members of the same faction are always friendly to each other.
+    pub fn reaction(&self, from: &str, to: &str) -> Reaction {
+        if from == to {
+            return Reaction::Friendly;
+        }
+        self.reactions
+            .get(from)
+            .and_then(|reactions| reactions.get(to))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// How one entity reacts to another. Entities without a faction are neutral.
+    pub fn reaction_between(&self, ecs: &impl EntityStore, from: Entity, to: Entity) -> Reaction {
+        let faction = |entity| {
+            ecs.entry_ref(entity)
+                .ok()
+                .and_then(|entry| entry.get_component::<Faction>().ok().cloned())
+        };
+        match (faction(from), faction(to)) {
+            (Some(from), Some(to)) => self.reaction(&from.0, &to.0),
+            _ => Reaction::Neutral,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> FactionTable {
+        let mut reactions = HashMap::new();
+        reactions.insert(
+            "Goblins".to_string(),
+            HashMap::from([("Player".to_string(), Reaction::Hostile)]),
+        );
+        reactions.insert(
+            "Orcs".to_string(),
+            HashMap::from([
+                ("Orcs".to_string(), Reaction::Hostile),
+                ("Goblins".to_string(), Reaction::Friendly),
+            ]),
+        );
+        FactionTable {
+            default: Reaction::Neutral,
+            reactions,
+        }
+    }
+
+    #[test]
+    fn listed_reactions_only_go_one_way() {
+        let table = table();
+        assert_eq!(table.reaction("Goblins", "Player"), Reaction::Hostile);
+        assert_eq!(table.reaction("Player", "Goblins"), Reaction::Neutral);
+        assert_eq!(table.reaction("Orcs", "Goblins"), Reaction::Friendly);
+    }
+
+    #[test]
+    fn unlisted_factions_get_the_default() {
+        let table = table();
+        assert_eq!(table.reaction("Beasts", "Player"), Reaction::Neutral);
+        assert_eq!(table.reaction("Goblins", "Beasts"), Reaction::Neutral);
+    }
+
+    #[test]
+    fn a_faction_is_always_friendly_to_itself() {
+        let table = table();
+        assert_eq!(table.reaction("Goblins", "Goblins"), Reaction::Friendly);
+        assert_eq!(table.reaction("Orcs", "Orcs"), Reaction::Friendly);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+
+/// The Dijkstra maps that monsters can follow
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlowField {
+    /// Distance to the player, for chasing
+    ToPlayer,
+    /// Lower values lead away from the player, while avoiding dead ends
+    Flee,
+    /// Distance to the nearest item lying on the floor
+    Items,
+}
+
+/// What the chase map is multiplied by to seed the flee map. Being negative, far
+/// tiles become the lowest; a magnitude above 1.0 makes distance outweigh the path
+/// there, so fleeing monsters run around the player instead of into corners.
+const FLEE_WEIGHT: f32 = -1.2;
+
+/// Flow fields shared by every monster, so that a turn with many monsters
+/// does not build the same Dijkstra map over and over again
+///
+/// The fields are only rebuilt when the player moves, items move, or the map changes
+/// (a new `FlowFields` is inserted with every new level). They are built lazily,
+/// so a field nobody asks for is never computed.
+pub struct FlowFields {
+    fields: HashMap<FlowField, DijkstraMap>,
+    player_pos: Option<Point>,
+    item_tiles: Vec<usize>,
+}
+
+impl FlowFields {
+    pub fn new() -> Self {
+        Self {
+            fields: HashMap::new(),
+            player_pos: None,
+            item_tiles: Vec::new(),
+        }
+    }
+
+    /// Record the current player and item positions, discarding any fields they invalidate
+    pub fn update(&mut self, player_pos: Point, mut item_tiles: Vec<usize>) {
+        if self.player_pos != Some(player_pos) {
+            self.player_pos = Some(player_pos);
+            self.fields.remove(&FlowField::ToPlayer);
+            self.fields.remove(&FlowField::Flee);
+        }
+
+        item_tiles.sort_unstable();
+        if self.item_tiles != item_tiles {
+            self.item_tiles = item_tiles;
+            self.fields.remove(&FlowField::Items);
+        }
+    }
+
+    /// The requested field, building it first if it is stale
+    pub fn get(&mut self, field: FlowField, map: &Map) -> &DijkstraMap {
+        if !self.fields.contains_key(&field) {
+            let dijkstra_map = self.build(field, map);
+            self.fields.insert(field, dijkstra_map);
+        }
+
+        &self.fields[&field]
+    }
+
+    /// The neighbouring tile with the lowest value in the given field
+    pub fn next_step(&mut self, field: FlowField, pos: Point, map: &Map) -> Option<Point> {
+        let idx = map.point2d_to_index(pos);
+        DijkstraMap::find_lowest_exit(self.get(field, map), idx, map)
+            .map(|exit| map.index_to_point2d(exit))
+    }
+
+    fn build(&mut self, field: FlowField, map: &Map) -> DijkstraMap {
+        match field {
+            FlowField::ToPlayer => {
+                let player_pos = self.player_pos.unwrap_or(Point::zero());
+                DijkstraMap::new(
+                    SCREEN_WIDTH,
+                    SCREEN_HEIGHT,
+                    &[map.point2d_to_index(player_pos)],
+                    map,
+                    1024.0,
+                )
+            }
+            FlowField::Flee => {
+                // Invert the chase map and let it settle again, so that fleeing
+                // monsters prefer escape routes over the nearest far-away corner
+                let starts: Vec<(usize, f32)> = self
+                    .get(FlowField::ToPlayer, map)
+                    .map
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, distance)| **distance < f32::MAX)
+                    .map(|(idx, distance)| (idx, distance * FLEE_WEIGHT))
+                    .collect();
+
+                let mut flee = DijkstraMap::new_empty(SCREEN_WIDTH, SCREEN_HEIGHT, 1024.0);
+                starts
+                    .iter()
+                    .for_each(|(idx, weight)| flee.map[*idx] = *weight);
+                DijkstraMap::build_weighted(&mut flee, &starts, map);
+                flee
+            }
+            FlowField::Items => {
+                DijkstraMap::new(SCREEN_WIDTH, SCREEN_HEIGHT, &self.item_tiles, map, 1024.0)
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::prelude::*;
+
+/// How full the player can get. Eating more than this is wasted.
+pub const MAX_SATIETY: i32 = 1500;
+
+/// Damage taken every turn while starving
+const STARVATION_DAMAGE: i32 = 1;
+
+/// How the player's hunger affects them, from best to worst
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HungerState {
+    WellFed,
+    Normal,
+    Hungry,
+    Weak,
+    Starving,
+}
+
+impl Hunger {
+    pub fn new() -> Self {
+        Self {
+            satiety: MAX_SATIETY,
+        }
+    }
+
+    pub fn state(&self) -> HungerState {
+        match self.satiety {
+            s if s > 1000 => HungerState::WellFed,
+            s if s > 300 => HungerState::Normal,
+            s if s > 150 => HungerState::Hungry,
+            s if s > 0 => HungerState::Weak,
+            _ => HungerState::Starving,
+        }
+    }
+
+    /// Get a turn hungrier, returning the damage that starving does
+    pub fn tick(&mut self) -> i32 {
+        self.satiety = i32::max(0, self.satiety - 1);
+        match self.state() {
+            HungerState::Starving => STARVATION_DAMAGE,
+            _ => 0,
+        }
+    }
+
+    pub fn eat(&mut self, nutrition: i32) {
+        self.satiety = i32::min(MAX_SATIETY, self.satiety + nutrition);
+    }
+}
+
+impl HungerState {
+    /// The name shown on the HUD, or `None` when there is nothing to worry about
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            HungerState::WellFed => Some("Well Fed"),
+            HungerState::Normal => None,
+            HungerState::Hungry => Some("Hungry"),
+            HungerState::Weak => Some("Weak"),
+            HungerState::Starving => Some("Starving"),
+        }
+    }
+
+    /// Whether natural healing stops, see `Regeneration`
+    pub fn stops_healing(self) -> bool {
+        matches!(self, HungerState::Weak | HungerState::Starving)
+    }
+
+    pub fn accuracy_penalty(self) -> i32 {
+        match self {
+            HungerState::WellFed | HungerState::Normal => 0,
+            HungerState::Hungry => 1,
+            HungerState::Weak | HungerState::Starving => 2,
+        }
+    }
+
+    pub fn damage_penalty(self) -> i32 {
+        match self {
+            HungerState::Weak | HungerState::Starving => 1,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunger(satiety: i32) -> Hunger {
+        Hunger { satiety }
+    }
+
+    #[test]
+    fn state_follows_satiety() {
+        assert_eq!(Hunger::new().state(), HungerState::WellFed);
+        assert_eq!(hunger(1001).state(), HungerState::WellFed);
+        assert_eq!(hunger(1000).state(), HungerState::Normal);
+        assert_eq!(hunger(301).state(), HungerState::Normal);
+        assert_eq!(hunger(300).state(), HungerState::Hungry);
+        assert_eq!(hunger(151).state(), HungerState::Hungry);
+        assert_eq!(hunger(150).state(), HungerState::Weak);
+        assert_eq!(hunger(1).state(), HungerState::Weak);
+        assert_eq!(hunger(0).state(), HungerState::Starving);
+    }
+
+    #[test]
+    fn tick_only_hurts_once_starving() {
+        let mut hunger = hunger(2);
+        assert_eq!(hunger.tick(), 0);
+        assert_eq!(hunger.satiety, 1);
+        assert_eq!(hunger.tick(), STARVATION_DAMAGE);
+        assert_eq!(hunger.satiety, 0);
+
+        // Satiety stops at zero, but the starving goes on
+        assert_eq!(hunger.tick(), STARVATION_DAMAGE);
+        assert_eq!(hunger.satiety, 0);
+    }
+
+    #[test]
+    fn eating_is_capped_at_max_satiety() {
+        let mut hunger = hunger(0);
+        hunger.eat(500);
+        assert_eq!(hunger.satiety, 500);
+        hunger.eat(MAX_SATIETY);
+        assert_eq!(hunger.satiety, MAX_SATIETY);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+/// What an item looks like until it is identified, chosen by the `appearance` field
+/// of its template
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Appearance {
+    Potion,
+    Scroll,
+}
+
+const POTION_COLORS: [&str; 10] = [
+    "Murky Red",
+    "Bubbling Green",
+    "Cloudy White",
+    "Fizzing Blue",
+    "Oily Black",
+    "Glowing Yellow",
+    "Smoky Purple",
+    "Swirling Orange",
+    "Milky Pink",
+    "Sparkling Silver",
+];
+
+const SCROLL_SYLLABLES: [&str; 16] = [
+    "ka", "zu", "mor", "eth", "vel", "xi", "bor", "nah", "qua", "ril", "sa", "tor", "um", "yel",
+    "fen", "dro",
+];
+
+/// Which kinds of items the player has identified, and what the others look like until then
+///
+/// Appearances are shuffled from the seed of the run, so they differ from one run to the
+/// next but stay the same on every level of a run.
+pub struct Identification {
+    /// The appearance of every kind of item that needs identifying, by its real name
+    appearances: HashMap<String, (Appearance, String)>,
+    /// Real names of the kinds of items that have been identified
+    identified: HashSet<String>,
+}
+
+impl Identification {
+    pub fn new(templates: &Templates, seed: u64) -> Self {
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        let mut colors = POTION_COLORS.to_vec();
+        let mut taken = HashSet::new();
+        let mut appearances = HashMap::new();
+
+        templates.entities.iter().for_each(|template| {
+            let Some(appearance) = template.appearance else {
+                return;
+            };
+            let name = match appearance {
+                // Should there ever be more potions than colors, the rest look alike
+                Appearance::Potion if colors.is_empty() => "Strange Potion".to_string(),
+                Appearance::Potion => {
+                    let color = colors.remove(rng.range(0, colors.len()));
+                    format!("{} Potion", color)
+                }
+                Appearance::Scroll => loop {
+                    let label: String = (0..rng.range(2, 4))
+                        .map(|_| SCROLL_SYLLABLES[rng.range(0, SCROLL_SYLLABLES.len())])
+                        .collect();
+                    if taken.insert(label.clone()) {
+                        break format!("Scroll labelled {}", label.to_uppercase());
+                    }
+                },
+            };
+            appearances.insert(template.name.clone(), (appearance, name));
+        });
+
+        Self {
+            appearances,
+            identified: HashSet::new(),
+        }
+    }
+
+    pub fn is_identified(&self, name: &str) -> bool {
+        !self.appearances.contains_key(name) || self.identified.contains(name)
+    }
+
+    pub fn identify(&mut self, name: &str) {
+        if self.appearances.contains_key(name) {
+            self.identified.insert(name.to_string());
+        }
+    }
+
+    /// The name to show for an item, which is its appearance until it is identified
+    pub fn name(&self, name: &str) -> String {
+        match self.appearances.get(name) {
+            Some((_, appearance)) if !self.is_identified(name) => appearance.clone(),
+            _ => name.to_string(),
+        }
+    }
+
+    /// What the inventory says about an item that has not been identified yet
+    pub fn unidentified_description(&self, name: &str) -> Option<&'static str> {
+        match self.appearances.get(name) {
+            Some(_) if self.is_identified(name) => None,
+            Some((Appearance::Potion, _)) => {
+                Some("You have not identified this potion. Drink it to find out what it does.")
+            }
+            Some((Appearance::Scroll, _)) => {
+                Some("You have not identified this scroll. Read it to find out what it does.")
+            }
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The real names of every item that starts out unidentified
+    fn unidentified(templates: &Templates) -> Vec<String> {
+        templates
+            .entities
+            .iter()
+            .filter(|template| template.appearance.is_some())
+            .map(|template| template.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn every_kind_of_item_looks_different() {
+        let templates = Templates::load();
+        let names = unidentified(&templates);
+        assert!(!names.is_empty());
+
+        (0..20).for_each(|seed| {
+            let identification = Identification::new(&templates, seed);
+            let appearances: HashSet<String> =
+                names.iter().map(|name| identification.name(name)).collect();
+            assert_eq!(appearances.len(), names.len());
+            assert!(names.iter().all(|name| !appearances.contains(name)));
+        });
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_appearances() {
+        let templates = Templates::load();
+        let first = Identification::new(&templates, 7);
+        let second = Identification::new(&templates, 7);
+        unidentified(&templates)
+            .iter()
+            .for_each(|name| assert_eq!(first.name(name), second.name(name)));
+    }
+
+    #[test]
+    fn identifying_reveals_only_that_kind_of_item() {
+        let templates = Templates::load();
+        let names = unidentified(&templates);
+        let mut identification = Identification::new(&templates, 1);
+        assert!(names.iter().all(|name| !identification.is_identified(name)));
+
+        identification.identify(&names[0]);
+        assert!(identification.is_identified(&names[0]));
+        assert_eq!(identification.name(&names[0]), names[0]);
+        assert!(identification.unidentified_description(&names[0]).is_none());
+        names[1..].iter().for_each(|name| {
+            assert!(!identification.is_identified(name));
+            assert!(identification.unidentified_description(name).is_some());
+        });
+    }
+
+    #[test]
+    fn items_without_an_appearance_are_always_identified() {
+        let templates = Templates::load();
+        let mut identification = Identification::new(&templates, 1);
+        assert!(identification.is_identified("Rusty Sword"));
+        assert_eq!(identification.name("Rusty Sword"), "Rusty Sword");
+
+        identification.identify("Rusty Sword");
+        assert!(identification
+            .unidentified_description("Rusty Sword")
+            .is_none());
+    }
+}
@@ -0,0 +1,292 @@
+use crate::prelude::*;
+use legion::world::EntryRef;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// How many slots the player's inventory has. A stack of identical items takes one slot.
+pub const INVENTORY_CAPACITY: usize = 20;
+
+/// One line of the inventory: a single item, or a stack of identical ones
+#[derive(Clone, Debug, PartialEq)]
+pub struct InventorySlot {
+    /// The real name of the items, whether or not they have been identified
+    pub name: String,
+    pub items: Vec<Entity>,
+    /// Whether more identical items go on this slot
+    pub stackable: bool,
+}
+
+impl InventorySlot {
+    /// The item that is used, equipped or dropped when the slot is picked
+    pub fn item(&self) -> Entity {
+        self.items[0]
+    }
+
+    /// The name shown in the inventory, with the stack size, where it is worn and its charges
+    pub fn label(&self, ecs: &impl EntityStore, identification: &Identification) -> String {
+        let mut label = identification.name(&self.name);
+        if self.items.len() > 1 {
+            label.push_str(&format!(" x{}", self.items.len()));
+        }
+        if let Ok(entry) = ecs.entry_ref(self.item()) {
+            if let Ok(equipped) = entry.get_component::<Equipped>() {
+                label.push_str(&format!(" ({})", equipped.slot.name()));
+            }
+            if let Ok(charges) = entry.get_component::<Charges>() {
+                label.push_str(&format!(" [{}]", charges.0));
+            }
+        }
+        label
+    }
+}
+
+/// The inventory screen's selection while in `TurnState::Inventory`
+pub struct InventoryMenu {
+    pub selected: usize,
+}
+
+impl InventoryMenu {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+}
+
+/// Whether identical copies of an item share a slot. Equipment and items with charges
+/// are kept apart, since no two of them are quite the same.
+fn is_stackable(entry: &EntryRef) -> bool {
+    entry.get_component::<Equippable>().is_err() && entry.get_component::<Charges>().is_err()
+}
+
+/// A key that orders entities the same way every time, as `Entity` itself has no order
+fn entity_key(entity: Entity) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    entity.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Everything an entity carries, with identical items stacked
+///
+/// Items are listed by name, so the order does not shift when one is put on or taken off.
+pub fn inventory_slots(ecs: &impl EntityStore, owner: Entity) -> Vec<InventorySlot> {
+    let mut items: Vec<(Entity, String)> = <(Entity, &Item, &Carried, &Name)>::query()
+        .iter(ecs)
+        .filter(|(_, _, carried, _)| carried.0 == owner)
+        .map(|(item, _, _, name)| (*item, name.0.clone()))
+        .collect();
+    items.sort_by(|(a, a_name), (b, b_name)| {
+        a_name
+            .cmp(b_name)
+            .then_with(|| entity_key(*a).cmp(&entity_key(*b)))
+    });
+
+    let mut slots: Vec<InventorySlot> = Vec::new();
+    items.into_iter().for_each(|(item, name)| {
+        let stackable = ecs.entry_ref(item).is_ok_and(|e| is_stackable(&e));
+        match slots
+            .iter_mut()
+            .find(|slot| stackable && slot.stackable && slot.name == name)
+        {
+            Some(slot) => slot.items.push(item),
+            None => slots.push(InventorySlot {
+                name,
+                items: vec![item],
+                stackable,
+            }),
+        }
+    });
+    slots
+}
+
+/// Puts an item into the inventory, if it fits on a stack or in a free slot. Returns
+/// whether it did.
+pub fn add_to_inventory(
+    ecs: &impl EntityStore,
+    slots: &mut Vec<InventorySlot>,
+    item: Entity,
+) -> bool {
+    let Ok(entry) = ecs.entry_ref(item) else {
+        return false;
+    };
+    let Ok(name) = entry.get_component::<Name>() else {
+        return false;
+    };
+    let stackable = is_stackable(&entry);
+
+    if let Some(slot) = slots
+        .iter_mut()
+        .find(|slot| stackable && slot.stackable && slot.name == name.0)
+    {
+        slot.items.push(item);
+        return true;
+    }
+    if slots.len() >= INVENTORY_CAPACITY {
+        return false;
+    }
+    slots.push(InventorySlot {
+        name: name.0.clone(),
+        items: vec![item],
+        stackable,
+    });
+    true
+}
+
+/// How far a carried item is thrown or zapped, or `None` if it needs no target
+///
+/// Ranged equipment, like bows, is fired with `F` once equipped instead.
+pub fn throw_range(ecs: &impl EntityStore, item: Entity) -> Option<i32> {
+    let entry = ecs.entry_ref(item).ok()?;
+    if entry.get_component::<Equippable>().is_ok() {
+        return None;
+    }
+    entry
+        .get_component::<Ranged>()
+        .ok()
+        .map(|ranged| ranged.range)
+}
+
+/// Use a carried item. Equipment is put on, or taken off if it is already worn.
+pub fn use_item(ecs: &impl EntityStore, commands: &mut CommandBuffer, owner: Entity, item: Entity) {
+    if let Ok(entry) = ecs.entry_ref(item) {
+        if entry.get_component::<Equipped>().is_ok() {
+            commands.push(((), UnequipItem { owner, item }));
+            return;
+        }
+        if entry.get_component::<Equippable>().is_ok() {
+            commands.push(((), EquipItem { owner, item }));
+            return;
+        }
+    }
+
+    // activate item to show its effect
+    commands.push((
+        (),
+        ActivateItem {
+            used_by: owner,
+            item,
+            target: None,
+        },
+    ));
+}
+
+/// Put a carried item down at the owner's feet, taking it off first if it is worn
+pub fn drop_item(
+    ecs: &impl EntityStore,
+    commands: &mut CommandBuffer,
+    owner: Entity,
+    item: Entity,
+    pos: Point,
+) {
+    if ecs
+        .entry_ref(item)
+        .is_ok_and(|e| e.get_component::<Equipped>().is_ok())
+    {
+        commands.push(((), UnequipItem { owner, item }));
+    }
+    commands.remove_component::<Carried>(item);
+    commands.add_component(item, pos);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn potion(world: &mut World, owner: Entity, name: &str) -> Entity {
+        world.push((Item, Carried(owner), Name(name.to_string())))
+    }
+
+    fn sword(world: &mut World, owner: Entity, name: &str) -> Entity {
+        world.push((
+            Item,
+            Carried(owner),
+            Name(name.to_string()),
+            Equippable(EquipmentSlot::MainHand),
+        ))
+    }
+
+    fn names(slots: &[InventorySlot]) -> Vec<&str> {
+        slots.iter().map(|slot| slot.name.as_str()).collect()
+    }
+
+    #[test]
+    fn identical_items_stack_but_equipment_does_not() {
+        let mut world = World::default();
+        let owner = world.push((Player { map_level: 0 },));
+        let first = potion(&mut world, owner, "Healing Potion");
+        let second = potion(&mut world, owner, "Healing Potion");
+        sword(&mut world, owner, "Rusty Sword");
+        sword(&mut world, owner, "Rusty Sword");
+        let other = world.push(());
+        potion(&mut world, other, "Healing Potion");
+
+        let slots = inventory_slots(&world, owner);
+        assert_eq!(
+            names(&slots),
+            vec!["Healing Potion", "Rusty Sword", "Rusty Sword"]
+        );
+        assert_eq!(slots[0].items.len(), 2);
+        assert!(slots[0].items.contains(&first) && slots[0].items.contains(&second));
+    }
+
+    #[test]
+    fn slots_keep_their_order_when_equipment_is_worn() {
+        let mut world = World::default();
+        let owner = world.push((Player { map_level: 0 },));
+        let swords = [
+            sword(&mut world, owner, "Rusty Sword"),
+            sword(&mut world, owner, "Rusty Sword"),
+        ];
+        potion(&mut world, owner, "Healing Potion");
+        sword(&mut world, owner, "Bow");
+
+        let before = inventory_slots(&world, owner);
+        assert_eq!(
+            names(&before),
+            vec!["Bow", "Healing Potion", "Rusty Sword", "Rusty Sword"]
+        );
+        swords.iter().for_each(|sword| {
+            world.entry(*sword).unwrap().add_component(Equipped {
+                owner,
+                slot: EquipmentSlot::MainHand,
+            });
+            assert_eq!(inventory_slots(&world, owner), before);
+            world.entry(*sword).unwrap().remove_component::<Equipped>();
+        });
+    }
+
+    #[test]
+    fn a_full_inventory_still_takes_items_that_stack() {
+        let mut world = World::default();
+        let owner = world.push((Player { map_level: 0 },));
+        potion(&mut world, owner, "Healing Potion");
+        (1..INVENTORY_CAPACITY).for_each(|i| {
+            sword(&mut world, owner, &format!("Sword {}", i));
+        });
+        let mut slots = inventory_slots(&world, owner);
+        assert_eq!(slots.len(), INVENTORY_CAPACITY);
+
+        let stacked = world.push((Item, Name("Healing Potion".to_string())));
+        assert!(add_to_inventory(&world, &mut slots, stacked));
+        assert_eq!(slots.len(), INVENTORY_CAPACITY);
+
+        let extra = world.push((Item, Name("Magic Map".to_string())));
+        assert!(!add_to_inventory(&world, &mut slots, extra));
+        let spare = world.push((
+            Item,
+            Name("Sword 1".to_string()),
+            Equippable(EquipmentSlot::MainHand),
+        ));
+        assert!(!add_to_inventory(&world, &mut slots, spare));
+        assert_eq!(slots.len(), INVENTORY_CAPACITY);
+    }
+
+    #[test]
+    fn an_item_goes_in_a_free_slot() {
+        let mut world = World::default();
+        let owner = world.push((Player { map_level: 0 },));
+        let mut slots = inventory_slots(&world, owner);
+        let item = world.push((Item, Name("Magic Map".to_string())));
+        assert!(add_to_inventory(&world, &mut slots, item));
+        assert_eq!(names(&slots), vec!["Magic Map"]);
+        assert!(slots[0].stackable);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::prelude::*;
+
+const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
+
+/// Light dimmer than this does not count as lit
+const MIN_BRIGHTNESS: f32 = 0.05;
+
+/// The color and brightness of the light falling on every tile of the map
+///
+/// The lighting system rebuilds it from every `LightSource` each turn. Light from
+/// several sources adds up, and fades with the distance to its source.
+pub struct LightMap {
+    light: Vec<RGB>,
+}
+
+impl LightMap {
+    pub fn new() -> Self {
+        Self {
+            light: vec![RGB::named(BLACK); NUM_TILES],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.light
+            .iter_mut()
+            .for_each(|light| *light = RGB::named(BLACK));
+    }
+
+    /// Light up the tiles a light source can see, fading towards the edge of its radius
+    pub fn add_light(&mut self, pos: Point, source: &LightSource, tiles: &VisibleTiles) {
+        tiles.iter().for_each(|tile| {
+            let distance = DistanceAlg::Pythagoras.distance2d(pos, tile);
+            if distance > source.radius as f32 {
+                return;
+            }
+
+            let intensity = 1.0 - distance / (source.radius + 1) as f32;
+            let light = &mut self.light[map_idx(tile.x, tile.y)];
+            light.r = (light.r + source.color.r * intensity).min(1.0);
+            light.g = (light.g + source.color.g * intensity).min(1.0);
+            light.b = (light.b + source.color.b * intensity).min(1.0);
+        });
+    }
+
+    /// The light falling on a tile, black if it is dark or outside the map
+    pub fn light_at(&self, pos: Point) -> RGB {
+        if pos.x >= 0 && pos.x < SCREEN_WIDTH && pos.y >= 0 && pos.y < SCREEN_HEIGHT {
+            self.light[map_idx(pos.x, pos.y)]
+        } else {
+            RGB::named(BLACK)
+        }
+    }
+
+    pub fn is_lit(&self, pos: Point) -> bool {
+        let light = self.light_at(pos);
+        light.r.max(light.g).max(light.b) > MIN_BRIGHTNESS
+    }
+}
@@ -1,10 +1,25 @@
 mod camera;
+mod cli;
+mod combat;
 mod components;
+mod energy;
+mod experience;
+mod factions;
+mod flow_fields;
+mod hunger;
+mod identification;
+mod inventory;
+mod lighting;
 mod map;
 mod map_builder;
+mod noise;
+mod shop;
+mod spatial_index;
 mod spawner;
 mod systems;
+mod targeting;
 mod turn_state;
+mod visible_tiles;
 
 mod prelude {
     pub use bracket_lib::prelude::*;
@@ -18,13 +33,29 @@ mod prelude {
     pub const SCREEN_HEIGHT: i32 = 50;
     pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
     pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
+    /// The deepest level, where the Amulet of Yendor waits instead of an exit
+    pub const AMULET_LEVEL: u32 = 2;
     pub use crate::camera::*;
+    pub use crate::combat::*;
     pub use crate::components::*;
+    pub use crate::energy::*;
+    pub use crate::experience::*;
+    pub use crate::factions::*;
+    pub use crate::flow_fields::*;
+    pub use crate::hunger::*;
+    pub use crate::identification::*;
+    pub use crate::inventory::*;
+    pub use crate::lighting::*;
     pub use crate::map::*;
     pub use crate::map_builder::*;
+    pub use crate::noise::*;
+    pub use crate::shop::*;
+    pub use crate::spatial_index::*;
     pub use crate::spawner::*;
     pub use crate::systems::*;
+    pub use crate::targeting::*;
     pub use crate::turn_state::*;
+    pub use crate::visible_tiles::*;
 }
 
 use std::collections::HashSet;
@@ -35,6 +66,9 @@ struct State {
     ecs: World,
     resources: Resources,
     input_systems: Schedule,
+    targeting_systems: Schedule,
+    inventory_systems: Schedule,
+    shop_systems: Schedule,
     player_systems: Schedule,
     monster_systems: Schedule,
 }
@@ -44,29 +78,92 @@ impl State {
         let mut ecs = World::default();
         let mut resources = Resources::default();
         let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        let mut map_builder = MapBuilder::new(&mut rng, 0);
 
         spawn_player(&mut ecs, map_builder.player_start);
         // spawn_amulet(&mut ecs, map_builder.amulet_start);
         let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
         map_builder.map.tiles[exit_idx] = TileType::Exit;
 
-        spawn_level(&mut ecs, &mut rng, 0, &map_builder.monster_spawns);
+        spawn_level(&mut ecs, &mut rng, 0, &map_builder);
 
         resources.insert(map_builder.map);
+        resources.insert(FlowFields::new());
+        resources.insert(SpatialIndex::new());
+        resources.insert(LightMap::new());
+        resources.insert(Targeting::new());
+        resources.insert(InventoryMenu::new());
+        resources.insert(ShopMenu::new());
         resources.insert(Camera::new(map_builder.player_start));
         resources.insert(TurnState::AwaitingInput);
+        resources.insert(Clock::new());
         resources.insert(map_builder.theme);
+        resources.insert(FactionTable::load());
+        resources.insert(Identification::new(&Templates::load(), rng.next_u64()));
 
         Self {
             ecs,
             resources,
             input_systems: build_input_scheduler(),
+            targeting_systems: build_targeting_scheduler(),
+            inventory_systems: build_inventory_scheduler(),
+            shop_systems: build_shop_scheduler(),
             player_systems: build_player_scheduler(),
             monster_systems: build_monster_scheduler(),
         }
     }
 
+    fn level_up(&mut self, ctx: &mut BTerm) {
+        let Some((player, experience)) = <(Entity, &Experience)>::query()
+            .filter(component::<Player>())
+            .iter(&self.ecs)
+            .map(|(entity, experience)| (*entity, *experience))
+            .next()
+        else {
+            return;
+        };
+
+        ctx.set_active_console(UI_CONSOLE_ID);
+        ctx.print_color_centered(
+            2,
+            GOLD,
+            BLACK,
+            format!("You have reached level {}!", experience.level + 1),
+        );
+        ctx.print_color_centered(4, WHITE, BLACK, "Choose a perk:");
+        for (i, perk) in Perk::ALL.iter().enumerate() {
+            ctx.print_color_centered(
+                6 + i as i32 * 2,
+                YELLOW,
+                BLACK,
+                format!("{} : {} ({})", i + 1, perk.name(), perk.description()),
+            );
+        }
+
+        let choice = match ctx.key {
+            Some(VirtualKeyCode::Key1) => Some(0),
+            Some(VirtualKeyCode::Key2) => Some(1),
+            Some(VirtualKeyCode::Key3) => Some(2),
+            Some(VirtualKeyCode::Key4) => Some(3),
+            Some(VirtualKeyCode::Key5) => Some(4),
+            _ => None,
+        };
+        if let Some(perk) = choice.and_then(|i| Perk::ALL.get(i)) {
+            level_up(&mut self.ecs, player, *perk);
+
+            // Enough experience for several levels means several choices
+            let can_level_up = self
+                .ecs
+                .entry_ref(player)
+                .ok()
+                .and_then(|entry| entry.get_component::<Experience>().ok().copied())
+                .is_some_and(|experience| experience.can_level_up());
+            if !can_level_up {
+                self.resources.insert(TurnState::AwaitingInput);
+            }
+        }
+    }
+
     fn game_over(&mut self, ctx: &mut BTerm) {
         ctx.set_active_console(UI_CONSOLE_ID);
         ctx.print_color_centered(2, RED, BLACK, "Your journey has ended.");
@@ -127,16 +224,26 @@ impl State {
         self.ecs = World::default();
         self.resources = Resources::default();
         let mut rng = RandomNumberGenerator::new();
-        let map_builder = MapBuilder::new(&mut rng);
+        let map_builder = MapBuilder::new(&mut rng, 0);
         spawn_player(&mut self.ecs, map_builder.player_start);
         spawn_amulet(&mut self.ecs, map_builder.amulet_start);
 
-        spawn_level(&mut self.ecs, &mut rng, 0, &map_builder.monster_spawns);
+        spawn_level(&mut self.ecs, &mut rng, 0, &map_builder);
 
         self.resources.insert(map_builder.map);
+        self.resources.insert(FlowFields::new());
+        self.resources.insert(SpatialIndex::new());
+        self.resources.insert(LightMap::new());
+        self.resources.insert(Targeting::new());
+        self.resources.insert(InventoryMenu::new());
+        self.resources.insert(ShopMenu::new());
         self.resources.insert(Camera::new(map_builder.player_start));
         self.resources.insert(TurnState::AwaitingInput);
+        self.resources.insert(Clock::new());
         self.resources.insert(map_builder.theme);
+        self.resources.insert(FactionTable::load());
+        self.resources
+            .insert(Identification::new(&Templates::load(), rng.next_u64()));
     }
 
     fn advance_level(&mut self) {
@@ -177,22 +284,31 @@ impl State {
             });
 
         // Create new level
+        let map_level = <&Player>::query()
+            .iter(&self.ecs)
+            .map(|player| player.map_level + 1)
+            .next()
+            .unwrap();
         let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        let mut map_builder = MapBuilder::new(&mut rng, map_level);
 
         // Move player to new start position
-        let mut map_level = 0;
         <(&mut Player, &mut Point)>::query()
             .iter_mut(&mut self.ecs)
             .for_each(|(player, pos)| {
-                player.map_level += 1;
-                map_level = player.map_level;
+                player.map_level = map_level;
                 pos.x = map_builder.player_start.x;
                 pos.y = map_builder.player_start.y;
             });
 
-        // On level 2, spawn the amulet
-        if map_level == 2 {
+        // The player arrives ready to act, whatever they last did
+        <&mut Energy>::query()
+            .filter(component::<Player>())
+            .iter_mut(&mut self.ecs)
+            .for_each(|energy| energy.current = energy.current.max(ENERGY_TO_ACT));
+
+        // On the deepest level, spawn the amulet
+        if map_level == AMULET_LEVEL {
             spawn_amulet(&mut self.ecs, map_builder.amulet_start);
         } else {
             let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
@@ -200,13 +316,14 @@ impl State {
         }
 
         // update resources
-        spawn_level(
-            &mut self.ecs,
-            &mut rng,
-            map_level as usize,
-            &map_builder.monster_spawns,
-        );
+        spawn_level(&mut self.ecs, &mut rng, map_level as usize, &map_builder);
         self.resources.insert(map_builder.map);
+        self.resources.insert(FlowFields::new());
+        self.resources.insert(SpatialIndex::new());
+        self.resources.insert(LightMap::new());
+        self.resources.insert(Targeting::new());
+        self.resources.insert(InventoryMenu::new());
+        self.resources.insert(ShopMenu::new());
         self.resources.insert(Camera::new(map_builder.player_start));
         self.resources.insert(TurnState::AwaitingInput);
         self.resources.insert(map_builder.theme);
@@ -225,6 +342,7 @@ impl GameState for State {
         self.resources.insert(ctx.key);
         ctx.set_active_console(BACKGROUND_CONSOLE_ID);
         self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
+        self.resources.insert(MouseClick(ctx.left_click));
 
         let current_state = *self.resources.get::<TurnState>().unwrap();
         match current_state {
@@ -232,6 +350,18 @@ impl GameState for State {
                 self.input_systems
                     .execute(&mut self.ecs, &mut self.resources);
             }
+            TurnState::Targeting => {
+                self.targeting_systems
+                    .execute(&mut self.ecs, &mut self.resources);
+            }
+            TurnState::Inventory => {
+                self.inventory_systems
+                    .execute(&mut self.ecs, &mut self.resources);
+            }
+            TurnState::Shop => {
+                self.shop_systems
+                    .execute(&mut self.ecs, &mut self.resources);
+            }
             TurnState::PlayerTurn => {
                 self.player_systems
                     .execute(&mut self.ecs, &mut self.resources);
@@ -240,6 +370,9 @@ impl GameState for State {
                 self.monster_systems
                     .execute(&mut self.ecs, &mut self.resources);
             }
+            TurnState::LevelUp => {
+                self.level_up(ctx);
+            }
             TurnState::GameOver => {
                 self.game_over(ctx);
             }
@@ -255,6 +388,12 @@ impl GameState for State {
 }
 
 fn main() -> BError {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        cli::run(&args);
+        return Ok(());
+    }
+
     let context = BTermBuilder::simple80x50()
         .with_title("Dungeon Crawler")
         .with_fps_cap(30.0)
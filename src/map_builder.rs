@@ -1,57 +1,135 @@
 mod automata;
+mod blueprint;
 mod drunkard;
 mod empty;
+mod handcrafted;
 mod prefab;
 mod rooms;
 mod themes;
+mod tiled;
 
 use crate::prelude::*;
+use handcrafted::HandcraftedArchitect;
+pub use handcrafted::LevelConfig;
 use prefab::apply_prefab;
+pub use themes::ThemeChoice;
+pub use tiled::TiledMap;
 
 const NUM_ROOMS: usize = 20;
+const NUM_LIGHTS: usize = 12;
 
 pub struct MapBuilder {
     pub map: Map,
     pub rooms: Vec<Rect>,
     pub monster_spawns: Vec<Point>,
+    /// Spawns of a specific template, e.g. the boss of a handcrafted level
+    pub named_spawns: Vec<(Point, String)>,
+    /// Cosmetic glyphs placed by handcrafted levels and vaults
+    pub decorations: Vec<(Point, Render)>,
     pub player_start: Point,
     pub amulet_start: Point,
     pub theme: Box<dyn MapTheme>,
 }
 
 impl MapBuilder {
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
+    pub fn new(rng: &mut RandomNumberGenerator, map_level: u32) -> Self {
+        // Use the handcrafted level for this depth, if there is one
+        let config = LevelConfig::load();
+        if let Some(level) = config.level_for(map_level) {
+            let mut architect = HandcraftedArchitect {
+                level: level.clone(),
+                config: config.clone(),
+            };
+            let mut mb = architect.design(rng);
+            mb.theme = match level.theme {
+                Some(theme) => theme.build(),
+                None => Self::random_theme(rng),
+            };
+            return mb;
+        }
+
         // Randomly choose an architect
-        let mut architect: Box<dyn MapArchitect> = match rng.range(0, 3) {
-            0 => Box::new(rooms::RoomsArchitect {}),
-            1 => Box::new(drunkard::DrunkardArchitect {}),
-            _ => Box::new(automata::CellularAutomataArchitect {}),
+        let architect = match rng.range(0, 3) {
+            0 => ArchitectChoice::Rooms,
+            1 => ArchitectChoice::Drunkard,
+            _ => ArchitectChoice::Automata,
         };
 
-        let mut mb = architect.design(rng);
-        apply_prefab(&mut mb, rng);
+        Self::with_architect(rng, architect, &config)
+    }
 
-        mb.theme = match rng.range(0, 2) {
-            0 => Box::new(themes::DungeonTheme {}),
-            _ => Box::new(themes::ForestTheme {}),
+    /// Generate a level with a specific architect, ignoring handcrafted levels
+    pub fn with_architect(
+        rng: &mut RandomNumberGenerator,
+        architect: ArchitectChoice,
+        config: &LevelConfig,
+    ) -> Self {
+        let mut architect: Box<dyn MapArchitect> = match architect {
+            ArchitectChoice::Rooms => Box::new(rooms::RoomsArchitect {}),
+            ArchitectChoice::Drunkard => Box::new(drunkard::DrunkardArchitect {}),
+            ArchitectChoice::Automata => Box::new(automata::CellularAutomataArchitect {}),
+            ArchitectChoice::Empty => Box::new(empty::EmptyArchitect {}),
         };
 
+        let mut mb = architect.design(rng);
+        apply_prefab(&mut mb, rng, config);
+
+        mb.theme = Self::random_theme(rng);
+        mb.place_lights(rng);
+
         mb
     }
 
+    fn random_theme(rng: &mut RandomNumberGenerator) -> Box<dyn MapTheme> {
+        match rng.range(0, 2) {
+            0 => themes::DungeonTheme::new(),
+            _ => themes::ForestTheme::new(),
+        }
+    }
+
+    /// Scatter the theme's light sources over the floor
+    fn place_lights(&mut self, rng: &mut RandomNumberGenerator) {
+        let Some(light) = self.theme.light_source() else {
+            return;
+        };
+
+        let mut floor: Vec<Point> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile == TileType::Floor)
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .filter(|pos| *pos != self.player_start && *pos != self.amulet_start)
+            .collect();
+
+        for _ in 0..NUM_LIGHTS {
+            let Some(idx) = rng.random_slice_index(&floor) else {
+                break;
+            };
+            self.named_spawns
+                .push((floor.remove(idx), light.to_string()));
+        }
+    }
+
     fn fill(&mut self, tile: TileType) {
         self.map.tiles.iter_mut().for_each(|t| *t = tile);
     }
 
-    /// Find the most distant point from the player start using Dijkstra Map
-    fn find_most_distant(&self) -> Point {
-        let dijkstra_map = DijkstraMap::new(
+    /// Distances from the player start to every reachable tile
+    fn start_distances(&self) -> DijkstraMap {
+        DijkstraMap::new(
             SCREEN_WIDTH,
             SCREEN_HEIGHT,
             &[self.map.point2d_to_index(self.player_start)],
             &self.map,
             1024.0,
-        );
+        )
+    }
+
+    /// Find the most distant point from the player start using Dijkstra Map
+    fn find_most_distant(&self) -> Point {
+        let dijkstra_map = self.start_distances();
 
         const UNREACHABLE: &f32 = &f32::MAX;
 
@@ -122,7 +200,7 @@ impl MapBuilder {
 
         // Sorting the rooms by their center x coordinate will connect
         // adjacent rooms and not snake all over the map
-        rooms.sort_by(|a, b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|a| a.center().x);
 
         for (i, room) in rooms.iter().enumerate().skip(1) {
             let prev = rooms[i - 1].center();
@@ -166,6 +244,27 @@ impl MapBuilder {
     }
 }
 
+/// An architect that can be picked by name, e.g. from the command line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArchitectChoice {
+    Rooms,
+    Drunkard,
+    Automata,
+    Empty,
+}
+
+impl ArchitectChoice {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rooms" => Some(Self::Rooms),
+            "drunkard" => Some(Self::Drunkard),
+            "automata" => Some(Self::Automata),
+            "empty" => Some(Self::Empty),
+            _ => None,
+        }
+    }
+}
+
 trait MapArchitect {
     fn design(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder;
 }
@@ -175,4 +274,9 @@ trait MapArchitect {
 /// since Legion resources may be accessed from multiple threads
 pub trait MapTheme: Sync + Send {
     fn tile_to_render(&self, tile: TileType) -> FontCharType;
+
+    /// The template scattered around generated levels to light them up
+    fn light_source(&self) -> Option<&'static str> {
+        None
+    }
 }
@@ -24,6 +24,8 @@ impl MapArchitect for CellularAutomataArchitect {
             map: Map::new(),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
+            named_spawns: Vec::new(),
+            decorations: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
             theme: Box::new(super::themes::DungeonTheme {}),
@@ -0,0 +1,179 @@
+use super::handcrafted::{LegendEntry, RexLegendEntry};
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+
+/// A level or vault layout, ready to be stamped onto a map
+///
+/// Blueprints can be read from ASCII text or from REXPaint `.xp` files,
+/// so that every layout source goes through the same legend.
+pub struct Blueprint {
+    pub width: i32,
+    pub height: i32,
+    /// Legend entries, relative to the top-left corner of the blueprint
+    pub cells: Vec<(Point, LegendEntry)>,
+    /// Purely cosmetic glyphs, relative to the top-left corner of the blueprint
+    pub decorations: Vec<(Point, Render)>,
+}
+
+impl Blueprint {
+    /// Read a blueprint from ASCII text, one map row per line
+    pub fn from_ascii(source: &str, legend: &HashMap<char, LegendEntry>) -> Self {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut cells = Vec::new();
+
+        for (y, line) in lines.iter().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                match legend.get(&ch) {
+                    Some(entry) => cells.push((Point::new(x as i32, y as i32), entry.clone())),
+                    // Spaces leave the underlying tile untouched
+                    None if ch == ' ' => {}
+                    None => println!("Unknown level character: {:?}", ch),
+                }
+            }
+        }
+
+        Self {
+            width: lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as i32,
+            height: lines.len() as i32,
+            cells,
+            decorations: Vec::new(),
+        }
+    }
+
+    /// Read a blueprint from a REXPaint file
+    ///
+    /// - Layer 0 holds the tiles,
+    /// - layer 1 holds the spawns (empty cells are skipped),
+    /// - layer 2 holds decorations, drawn with the glyph and color of the cell.
+    pub fn from_rex(
+        path: &str,
+        rex_legend: &[RexLegendEntry],
+        legend: &HashMap<char, LegendEntry>,
+    ) -> Self {
+        let mut file = File::open(path).expect("Failed to open REXPaint file");
+        let xp = XpFile::read(&mut file).expect("Failed to parse REXPaint file");
+
+        let mut blueprint = Self {
+            width: xp.layers.first().map(|l| l.width).unwrap_or(0) as i32,
+            height: xp.layers.first().map(|l| l.height).unwrap_or(0) as i32,
+            cells: Vec::new(),
+            decorations: Vec::new(),
+        };
+
+        for (layer_idx, layer) in xp.layers.iter().enumerate().take(3) {
+            for y in 0..layer.height {
+                for x in 0..layer.width {
+                    let cell = layer.get(x, y).unwrap();
+                    let pos = Point::new(x as i32, y as i32);
+
+                    // REXPaint leaves untouched cells as blanks on a transparent background
+                    if layer_idx > 0 && (cell.ch == 0 || cell.ch == 32) {
+                        continue;
+                    }
+
+                    if layer_idx == 2 {
+                        blueprint.decorations.push((
+                            pos,
+                            Render {
+                                color: ColorPair::new(
+                                    RGB::from_u8(cell.fg.r, cell.fg.g, cell.fg.b),
+                                    BLACK,
+                                ),
+                                glyph: cell.ch as FontCharType,
+                            },
+                        ));
+                        continue;
+                    }
+
+                    let color = (cell.fg.r, cell.fg.g, cell.fg.b);
+                    match lookup_rex_cell(cell.ch, color, rex_legend, legend) {
+                        Some(entry) => blueprint.cells.push((pos, entry)),
+                        None => {
+                            println!("Unknown REXPaint cell {} at {:?} in {}", cell.ch, pos, path)
+                        }
+                    }
+                }
+            }
+        }
+
+        blueprint
+    }
+
+    /// The first cell matching the given legend entry
+    pub fn find(&self, entry: &LegendEntry) -> Option<Point> {
+        self.cells
+            .iter()
+            .find(|(_, e)| e == entry)
+            .map(|(pos, _)| *pos)
+    }
+}
+
+/// Find the legend entry for a REXPaint cell
+///
+/// Entries that specify a color must match it exactly and take priority over
+/// glyph-only entries. Cells without a REXPaint-specific entry fall back to the
+/// ASCII legend, so artists can use the same characters as plain-text levels.
+fn lookup_rex_cell(
+    glyph: u32,
+    color: (u8, u8, u8),
+    rex_legend: &[RexLegendEntry],
+    legend: &HashMap<char, LegendEntry>,
+) -> Option<LegendEntry> {
+    let matches_glyph = |e: &&RexLegendEntry| to_cp437(e.glyph) as u32 == glyph;
+
+    rex_legend
+        .iter()
+        .filter(matches_glyph)
+        .find(|e| e.color == Some(color))
+        .or_else(|| {
+            rex_legend
+                .iter()
+                .filter(matches_glyph)
+                .find(|e| e.color.is_none())
+        })
+        .map(|e| e.entry.clone())
+        .or_else(|| {
+            legend
+                .iter()
+                .find(|(ch, _)| to_cp437(**ch) as u32 == glyph)
+                .map(|(_, entry)| entry.clone())
+        })
+}
+
+impl MapBuilder {
+    /// Stamp a blueprint onto the map with its top-left corner at `origin`
+    ///
+    /// Spawns are added to the builder, while the player start and exit are
+    /// only carved as floor. Callers decide what to do with those, since a vault
+    /// should not move the player.
+    pub fn stamp(&mut self, blueprint: &Blueprint, origin: Point) {
+        for (offset, entry) in blueprint.cells.iter() {
+            let pos = origin + *offset;
+            let Some(idx) = self.map.try_idx(pos) else {
+                continue;
+            };
+            match entry {
+                LegendEntry::Wall => self.map.tiles[idx] = TileType::Wall,
+                LegendEntry::Floor | LegendEntry::Exit | LegendEntry::PlayerStart => {
+                    self.map.tiles[idx] = TileType::Floor
+                }
+                LegendEntry::Monster => {
+                    self.map.tiles[idx] = TileType::Floor;
+                    self.monster_spawns.push(pos);
+                }
+                LegendEntry::Spawn(name) => {
+                    self.map.tiles[idx] = TileType::Floor;
+                    self.named_spawns.push((pos, name.clone()));
+                }
+            }
+        }
+
+        for (offset, render) in blueprint.decorations.iter() {
+            let pos = origin + *offset;
+            if self.map.in_bounds(pos) {
+                self.decorations.push((pos, *render));
+            }
+        }
+    }
+}
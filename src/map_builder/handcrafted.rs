@@ -0,0 +1,127 @@
+use super::{blueprint::Blueprint, themes::ThemeChoice, MapArchitect};
+use crate::prelude::*;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+
+/// What a single character of a handcrafted level stands for
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum LegendEntry {
+    Wall,
+    Floor,
+    /// The exit stairs, or the amulet on the last level
+    Exit,
+    PlayerStart,
+    /// A random spawn drawn from the templates for the current depth
+    Monster,
+    /// A spawn of the template with the given name, e.g. "Ettin"
+    Spawn(String),
+}
+
+/// A REXPaint-specific legend entry
+///
+/// REXPaint cells carry a color as well as a glyph, so the same glyph can stand for
+/// different things, e.g. a green `g` for a goblin and a red `g` for something nastier.
+#[derive(Deserialize, Clone, Debug)]
+pub struct RexLegendEntry {
+    pub glyph: char,
+    /// The foreground color the cell must have, or any color if `None`
+    pub color: Option<(u8, u8, u8)>,
+    pub entry: LegendEntry,
+}
+
+/// A single level authored by hand
+#[derive(Deserialize, Clone, Debug)]
+pub struct HandcraftedLevel {
+    /// Path of the level file, relative to `resources/levels/`.
+    /// Files ending in `.xp` are read as REXPaint images, files ending in `.json`
+    /// as Tiled maps, and anything else as ASCII.
+    pub file: String,
+    pub theme: Option<ThemeChoice>,
+}
+
+/// Maps dungeon depths to handcrafted levels, loaded from `resources/levels.ron`
+#[derive(Deserialize, Clone, Debug)]
+pub struct LevelConfig {
+    pub legend: HashMap<char, LegendEntry>,
+    pub rex_legend: Vec<RexLegendEntry>,
+    pub levels: HashMap<u32, HandcraftedLevel>,
+    /// REXPaint vaults, relative to `resources/prefabs/`
+    pub vaults: Vec<String>,
+}
+
+impl LevelConfig {
+    pub fn load() -> Self {
+        let file = File::open("resources/levels.ron").expect("Failed to open level config file");
+
+        from_reader(file).expect("Failed to parse level config file")
+    }
+
+    /// The handcrafted level for the given depth, if there is one
+    pub fn level_for(&self, map_level: u32) -> Option<&HandcraftedLevel> {
+        self.levels.get(&map_level)
+    }
+
+    /// Load a level or vault file with this config's legends
+    pub fn load_blueprint(&self, path: &str) -> Blueprint {
+        if path.ends_with(".xp") {
+            Blueprint::from_rex(path, &self.rex_legend, &self.legend)
+        } else if path.ends_with(".json") {
+            Blueprint::from_tiled(path, &self.legend)
+        } else {
+            let source = fs::read_to_string(path).expect("Failed to open level file");
+            Blueprint::from_ascii(&source, &self.legend)
+        }
+    }
+}
+
+/// An architect that loads a whole level from an ASCII or REXPaint file
+///
+/// The level is centered on the map, and everything outside of it is filled with walls.
+pub struct HandcraftedArchitect {
+    pub level: HandcraftedLevel,
+    pub config: LevelConfig,
+}
+
+impl MapArchitect for HandcraftedArchitect {
+    fn design(&mut self, _rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            named_spawns: Vec::new(),
+            decorations: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: super::themes::DungeonTheme::new(),
+        };
+
+        mb.fill(TileType::Wall);
+
+        let path = format!("resources/levels/{}", self.level.file);
+        let blueprint = self.config.load_blueprint(&path);
+        if blueprint.width > SCREEN_WIDTH || blueprint.height > SCREEN_HEIGHT {
+            panic!("Level {} is larger than the map", path);
+        }
+
+        let origin = Point::new(
+            (SCREEN_WIDTH - blueprint.width) / 2,
+            (SCREEN_HEIGHT - blueprint.height) / 2,
+        );
+        mb.stamp(&blueprint, origin);
+
+        mb.player_start = origin
+            + blueprint
+                .find(&LegendEntry::PlayerStart)
+                .expect("Level has no player start");
+
+        // Levels without an explicit exit get one as far away from the player as possible
+        mb.amulet_start = match blueprint.find(&LegendEntry::Exit) {
+            Some(exit) => origin + exit,
+            None => mb.find_most_distant(),
+        };
+
+        mb
+    }
+}
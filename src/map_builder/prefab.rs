@@ -1,4 +1,9 @@
+use super::{
+    blueprint::Blueprint,
+    handcrafted::{LegendEntry, LevelConfig},
+};
 use crate::prelude::*;
+use std::collections::HashMap;
 
 const FORTRESS: (&str, i32, i32) = (
     "
@@ -18,24 +23,69 @@ const FORTRESS: (&str, i32, i32) = (
     11,
 );
 
-pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+const SHOP: (&str, i32, i32) = (
+    "
+-----------
+-#########-
+-#t-----t#-
+-#-------#-
+-#---h---#-
+-#-------#-
+-####-####-
+-----------
+",
+    11,
+    8,
+);
+
+/// Reads one of the built-in ASCII vaults with its own legend
+fn built_in(vault: (&str, i32, i32), legend: &HashMap<char, LegendEntry>) -> Blueprint {
+    let mut blueprint = Blueprint::from_ascii(vault.0.trim(), legend);
+    blueprint.width = vault.1;
+    blueprint.height = vault.2;
+    blueprint
+}
+
+/// The built-in fortress vault
+fn fortress() -> Blueprint {
+    let legend = HashMap::from([
+        ('#', LegendEntry::Wall),
+        ('-', LegendEntry::Floor),
+        ('M', LegendEntry::Monster),
+    ]);
+    built_in(FORTRESS, &legend)
+}
+
+/// A lit room with a shopkeeper in it, which stocks up on items from the level's depth
+fn shop() -> Blueprint {
+    let legend = HashMap::from([
+        ('#', LegendEntry::Wall),
+        ('-', LegendEntry::Floor),
+        ('t', LegendEntry::Spawn("Torch".to_string())),
+        ('h', LegendEntry::Spawn("Shopkeeper".to_string())),
+    ]);
+    built_in(SHOP, &legend)
+}
+
+pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, config: &LevelConfig) {
+    // Pick between the built-in fortress and shop, and the REXPaint vaults
+    let mut vaults = vec![fortress(), shop()];
+    config.vaults.iter().for_each(|file| {
+        vaults.push(config.load_blueprint(&format!("resources/prefabs/{}", file)));
+    });
+    let vault = rng.random_slice_entry(&vaults).unwrap();
+
     let mut placements = None;
 
-    let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        &[mb.map.point2d_to_index(mb.player_start)],
-        &mb.map,
-        1024.0,
-    );
+    let dijkstra_map = mb.start_distances();
 
     let mut attempts = 0;
     while placements.is_none() && attempts < 10 {
         let dimensions = Rect::with_size(
-            rng.range(0, SCREEN_WIDTH - FORTRESS.1),
-            rng.range(0, SCREEN_HEIGHT - FORTRESS.2),
-            FORTRESS.1,
-            FORTRESS.2,
+            rng.range(0, SCREEN_WIDTH - vault.width),
+            rng.range(0, SCREEN_HEIGHT - vault.height),
+            vault.width,
+            vault.height,
         );
 
         let mut can_place = false;
@@ -62,21 +112,6 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
     }
 
     if let Some(origin) = placements {
-        for (y, line) in FORTRESS.0.trim().lines().enumerate() {
-            for (x, ch) in line.chars().enumerate() {
-                let map_x = origin.x + x as i32;
-                let map_y = origin.y + y as i32;
-                let idx = map_idx(map_x, map_y);
-                match ch {
-                    '#' => mb.map.tiles[idx] = TileType::Wall,
-                    '-' => mb.map.tiles[idx] = TileType::Floor,
-                    'M' => {
-                        mb.map.tiles[idx] = TileType::Floor;
-                        mb.monster_spawns.push(Point::new(map_x, map_y));
-                    }
-                    _ => {}
-                }
-            }
-        }
+        mb.stamp(vault, origin);
     }
 }
@@ -1,8 +1,34 @@
 use crate::prelude::*;
+use serde::Deserialize;
+
+/// A theme that can be picked by name, e.g. from a level config
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ThemeChoice {
+    Dungeon,
+    Forest,
+}
+
+impl ThemeChoice {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dungeon" => Some(Self::Dungeon),
+            "forest" => Some(Self::Forest),
+            _ => None,
+        }
+    }
+
+    pub fn build(self) -> Box<dyn MapTheme> {
+        match self {
+            ThemeChoice::Dungeon => DungeonTheme::new(),
+            ThemeChoice::Forest => ForestTheme::new(),
+        }
+    }
+}
 
 pub struct DungeonTheme {}
 
 impl DungeonTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self {})
     }
@@ -16,11 +42,16 @@ impl MapTheme for DungeonTheme {
             TileType::Exit => to_cp437('>'),
         }
     }
+
+    fn light_source(&self) -> Option<&'static str> {
+        Some("Torch")
+    }
 }
 
 pub struct ForestTheme {}
 
 impl ForestTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self {})
     }
@@ -34,4 +65,7 @@ impl MapTheme for ForestTheme {
             TileType::Exit => to_cp437('>'),
         }
     }
+    fn light_source(&self) -> Option<&'static str> {
+        Some("Glowing Fungus")
+    }
 }
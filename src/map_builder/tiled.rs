@@ -0,0 +1,266 @@
+use super::{blueprint::Blueprint, handcrafted::LegendEntry, themes::DungeonTheme};
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+
+/// Size of a single tile in `dungeonfont.png`, in pixels
+const TILE_SIZE: i32 = 32;
+
+/// A Tiled map in its JSON format
+///
+/// Only the parts we need are modelled: a single tile layer for the map and
+/// an object layer for the player start, exit and spawns.
+/// See <https://doc.mapeditor.org/en/stable/reference/json-map-format/>
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TiledMap {
+    #[serde(rename = "type")]
+    pub map_type: String,
+    pub version: String,
+    pub orientation: String,
+    pub renderorder: String,
+    pub infinite: bool,
+    pub width: i32,
+    pub height: i32,
+    pub tilewidth: i32,
+    pub tileheight: i32,
+    pub nextlayerid: u32,
+    pub nextobjectid: u32,
+    pub layers: Vec<TiledLayer>,
+    pub tilesets: Vec<TiledTileset>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum TiledLayer {
+    #[serde(rename = "tilelayer")]
+    TileLayer {
+        id: u32,
+        name: String,
+        width: i32,
+        height: i32,
+        x: i32,
+        y: i32,
+        opacity: f32,
+        visible: bool,
+        data: Vec<u32>,
+    },
+    #[serde(rename = "objectgroup")]
+    ObjectGroup {
+        id: u32,
+        name: String,
+        x: i32,
+        y: i32,
+        opacity: f32,
+        visible: bool,
+        draworder: String,
+        objects: Vec<TiledObject>,
+    },
+}
+
+/// An object on the object layer, positioned in pixels
+///
+/// The `type` is one of `PlayerStart`, `Exit`, `Monster` or `Spawn`.
+/// For `Spawn` objects, the name is the template to spawn.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    /// Tiled 1.9 saved this field as `class`
+    #[serde(rename = "type", alias = "class")]
+    pub object_type: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub rotation: f32,
+    pub visible: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct TiledTileset {
+    pub firstgid: u32,
+    pub name: String,
+    pub image: String,
+    pub imagewidth: i32,
+    pub imageheight: i32,
+    pub tilewidth: i32,
+    pub tileheight: i32,
+    pub tilecount: u32,
+    pub columns: u32,
+    pub margin: i32,
+    pub spacing: i32,
+}
+
+impl TiledMap {
+    /// Export a generated level
+    ///
+    /// Tiles are written with the dungeon theme glyphs, so that the level
+    /// legend can read them back when the map is imported again.
+    pub fn from_builder(mb: &MapBuilder) -> Self {
+        let theme = DungeonTheme::new();
+        let tileset = TiledTileset::dungeon_font();
+
+        let data = mb
+            .map
+            .tiles
+            .iter()
+            .map(|tile| theme.tile_to_render(*tile) as u32 + tileset.firstgid)
+            .collect();
+
+        let mut objects = Vec::new();
+        let mut push_object = |pos: Point, name: &str, object_type: &str| {
+            objects.push(TiledObject {
+                id: objects.len() as u32 + 1,
+                name: name.to_string(),
+                object_type: object_type.to_string(),
+                x: (pos.x * TILE_SIZE) as f32,
+                y: (pos.y * TILE_SIZE) as f32,
+                width: TILE_SIZE as f32,
+                height: TILE_SIZE as f32,
+                rotation: 0.0,
+                visible: true,
+            });
+        };
+
+        push_object(mb.player_start, "Player", "PlayerStart");
+        push_object(mb.amulet_start, "Exit", "Exit");
+        mb.monster_spawns
+            .iter()
+            .for_each(|pos| push_object(*pos, "Monster", "Monster"));
+        mb.named_spawns
+            .iter()
+            .for_each(|(pos, name)| push_object(*pos, name, "Spawn"));
+
+        let nextobjectid = objects.len() as u32 + 1;
+
+        Self {
+            map_type: "map".to_string(),
+            version: "1.10".to_string(),
+            orientation: "orthogonal".to_string(),
+            renderorder: "right-down".to_string(),
+            infinite: false,
+            width: SCREEN_WIDTH,
+            height: SCREEN_HEIGHT,
+            tilewidth: TILE_SIZE,
+            tileheight: TILE_SIZE,
+            nextlayerid: 3,
+            nextobjectid,
+            layers: vec![
+                TiledLayer::TileLayer {
+                    id: 1,
+                    name: "tiles".to_string(),
+                    width: SCREEN_WIDTH,
+                    height: SCREEN_HEIGHT,
+                    x: 0,
+                    y: 0,
+                    opacity: 1.0,
+                    visible: true,
+                    data,
+                },
+                TiledLayer::ObjectGroup {
+                    id: 2,
+                    name: "spawns".to_string(),
+                    x: 0,
+                    y: 0,
+                    opacity: 1.0,
+                    visible: true,
+                    draworder: "topdown".to_string(),
+                    objects,
+                },
+            ],
+            tilesets: vec![tileset],
+        }
+    }
+
+    pub fn save(&self, path: &str) {
+        let file = File::create(path).expect("Failed to create Tiled map file");
+        serde_json::to_writer_pretty(file, self).expect("Failed to write Tiled map file");
+    }
+
+    pub fn load(path: &str) -> Self {
+        let file = File::open(path).expect("Failed to open Tiled map file");
+        serde_json::from_reader(file).expect("Failed to parse Tiled map file")
+    }
+}
+
+impl TiledTileset {
+    /// The font the game renders the map with, as seen from `resources/levels/`
+    fn dungeon_font() -> Self {
+        Self {
+            firstgid: 1,
+            name: "dungeonfont".to_string(),
+            image: "../dungeonfont.png".to_string(),
+            imagewidth: 512,
+            imageheight: 512,
+            tilewidth: TILE_SIZE,
+            tileheight: TILE_SIZE,
+            tilecount: 256,
+            columns: 16,
+            margin: 0,
+            spacing: 0,
+        }
+    }
+}
+
+impl Blueprint {
+    /// Read a blueprint from a Tiled JSON map
+    ///
+    /// Tile glyphs are looked up in the ASCII legend, and the object layer
+    /// provides the player start, exit and spawns.
+    pub fn from_tiled(path: &str, legend: &HashMap<char, LegendEntry>) -> Self {
+        let tiled = TiledMap::load(path);
+        let firstgid = tiled.tilesets.first().map(|t| t.firstgid).unwrap_or(1);
+
+        let mut blueprint = Self {
+            width: tiled.width,
+            height: tiled.height,
+            cells: Vec::new(),
+            decorations: Vec::new(),
+        };
+
+        for layer in tiled.layers.iter() {
+            match layer {
+                TiledLayer::TileLayer { width, data, .. } => {
+                    for (idx, gid) in data.iter().enumerate() {
+                        // A gid of zero is an empty cell
+                        if *gid < firstgid {
+                            continue;
+                        }
+                        let pos = Point::new(idx as i32 % width, idx as i32 / width);
+                        let glyph = to_char((*gid - firstgid) as u8);
+                        match legend.get(&glyph) {
+                            Some(entry) => blueprint.cells.push((pos, entry.clone())),
+                            None => {
+                                println!("Unknown Tiled tile {:?} at {:?} in {}", glyph, pos, path)
+                            }
+                        }
+                    }
+                }
+                TiledLayer::ObjectGroup { objects, .. } => {
+                    for object in objects.iter() {
+                        let pos = Point::new(
+                            object.x as i32 / tiled.tilewidth,
+                            object.y as i32 / tiled.tileheight,
+                        );
+                        let entry = match object.object_type.as_str() {
+                            "PlayerStart" => LegendEntry::PlayerStart,
+                            "Exit" => LegendEntry::Exit,
+                            "Monster" => LegendEntry::Monster,
+                            "Spawn" => LegendEntry::Spawn(object.name.clone()),
+                            _ => {
+                                println!("Unknown Tiled object type: {}", object.object_type);
+                                continue;
+                            }
+                        };
+                        blueprint.cells.push((pos, entry));
+                    }
+                }
+            }
+        }
+
+        blueprint
+    }
+}
@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+/// How loud a step is
+pub const WALKING_NOISE: i32 = 3;
+
+/// How much quieter sneaking makes a step
+const SNEAKING_REDUCTION: i32 = 2;
+
+/// How loud a fight is
+pub const COMBAT_NOISE: i32 = 8;
+
+/// Noise at least this loud wakes sleeping monsters. Awake ones hear anything at all.
+pub const WAKE_THRESHOLD: i32 = 4;
+
+/// The noise an entity makes taking a step, including what its gear adds or muffles
+pub fn walking_noise(ecs: &impl EntityStore, entity: Entity) -> i32 {
+    let mut noise = WALKING_NOISE;
+    if let Ok(entry) = ecs.entry_ref(entity) {
+        if entry.get_component::<Sneaking>().is_ok() {
+            noise -= SNEAKING_REDUCTION;
+        }
+        if let Ok(modifier) = entry.get_component::<NoiseModifier>() {
+            noise += modifier.0;
+        }
+    }
+
+    <(&Equipped, &NoiseModifier)>::query()
+        .iter(ecs)
+        .filter(|(equipped, _)| equipped.owner == entity)
+        .for_each(|(_, modifier)| noise += modifier.0);
+
+    i32::max(0, noise)
+}
+
+/// How loud a noise still is after travelling some distance, or zero once it has died out
+pub fn noise_at_distance(volume: i32, distance: f32) -> i32 {
+    i32::max(0, volume - distance.ceil() as i32)
+}
@@ -0,0 +1,90 @@
+use crate::prelude::*;
+
+/// The faction a shopkeeper joins once the player attacks it, which is hostile to the player
+pub const ANGRY_SHOPKEEPER: &str = "Angry Merchants";
+
+/// The shop screen while in `TurnState::Shop`
+pub struct ShopMenu {
+    /// Who the player is trading with
+    pub shopkeeper: Option<Entity>,
+    pub selected: usize,
+    /// Whether the screen lists the player's items to sell, rather than the shop's to buy
+    pub selling: bool,
+    /// What became of the last trade, shown until the next one
+    pub message: Option<String>,
+}
+
+impl ShopMenu {
+    pub fn new() -> Self {
+        Self {
+            shopkeeper: None,
+            selected: 0,
+            selling: false,
+            message: None,
+        }
+    }
+
+    /// Starts trading with a shopkeeper, on the list of items it sells
+    pub fn open(shopkeeper: Entity) -> Self {
+        Self {
+            shopkeeper: Some(shopkeeper),
+            ..Self::new()
+        }
+    }
+}
+
+/// What a shop charges for an item, or `None` if shops do not deal in it
+pub fn buy_price(ecs: &impl EntityStore, item: Entity) -> Option<i32> {
+    ecs.entry_ref(item)
+        .ok()
+        .and_then(|entry| entry.get_component::<Value>().ok().map(|value| value.0))
+}
+
+/// What a shop pays for an item, which is half of what it charges
+pub fn sell_price(ecs: &impl EntityStore, item: Entity) -> Option<i32> {
+    buy_price(ecs, item).map(|price| i32::max(1, price / 2))
+}
+
+/// The gold an entity has, which is none if it has no purse
+pub fn gold_of(ecs: &impl EntityStore, entity: Entity) -> i32 {
+    ecs.entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Gold>().ok().map(|gold| gold.0))
+        .unwrap_or(0)
+}
+
+/// Add gold to an entity's purse, or take it out if the amount is negative
+pub fn add_gold(ecs: &impl EntityStore, commands: &mut CommandBuffer, entity: Entity, amount: i32) {
+    let gold = gold_of(ecs, entity);
+    commands.add_component(entity, Gold(gold + amount));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shops_pay_half_of_what_they_charge() {
+        let mut world = World::default();
+        let sword = world.push((Item, Value(30)));
+        let potion = world.push((Item, Value(15)));
+        assert_eq!(buy_price(&world, sword), Some(30));
+        assert_eq!(sell_price(&world, sword), Some(15));
+        assert_eq!(sell_price(&world, potion), Some(7));
+    }
+
+    #[test]
+    fn cheap_items_still_sell_for_a_coin() {
+        let mut world = World::default();
+        let trinket = world.push((Item, Value(1)));
+        assert_eq!(sell_price(&world, trinket), Some(1));
+    }
+
+    #[test]
+    fn items_without_a_value_are_not_traded() {
+        let mut world = World::default();
+        let amulet = world.push((Item,));
+        assert_eq!(buy_price(&world, amulet), None);
+        assert_eq!(sell_price(&world, amulet), None);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::prelude::*;
+
+const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
+
+/// An entity standing on a tile of the map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Occupant {
+    pub entity: Entity,
+    /// Whether nothing else may enter the tile while this entity is on it
+    pub blocks: bool,
+    pub is_item: bool,
+}
+
+/// An index of which entities are on which tile
+///
+/// Answering "what is on this tile?" with a query means scanning every entity with
+/// a position. The index answers it by looking at a single tile instead, which
+/// keeps monster AI cheap on big maps with lots of entities.
+///
+/// The index is rebuilt at the start of every schedule, and the movement system
+/// keeps it up to date as entities move.
+pub struct SpatialIndex {
+    tiles: Vec<Vec<Occupant>>,
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        Self {
+            tiles: vec![Vec::new(); NUM_TILES],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.iter_mut().for_each(|tile| tile.clear());
+    }
+
+    pub fn insert(&mut self, pos: Point, occupant: Occupant) {
+        if let Some(tile) = self.tile_mut(pos) {
+            tile.push(occupant);
+        }
+    }
+
+    /// Move an entity from one tile to another
+    pub fn move_entity(&mut self, entity: Entity, from: Point, to: Point) {
+        let occupant = self.tile_mut(from).and_then(|tile| {
+            tile.iter()
+                .position(|o| o.entity == entity)
+                .map(|idx| tile.remove(idx))
+        });
+
+        if let Some(occupant) = occupant {
+            self.insert(to, occupant);
+        }
+    }
+
+    /// Everything on the given tile
+    pub fn entities_at(&self, pos: Point) -> &[Occupant] {
+        self.tile(pos).unwrap_or(&[])
+    }
+
+    /// The entity blocking the given tile, if any
+    pub fn blocker_at(&self, pos: Point) -> Option<Entity> {
+        self.entities_at(pos)
+            .iter()
+            .find(|o| o.blocks)
+            .map(|o| o.entity)
+    }
+
+    /// The items lying on the given tile
+    pub fn items_at(&self, pos: Point) -> impl Iterator<Item = Entity> + '_ {
+        self.entities_at(pos)
+            .iter()
+            .filter(|o| o.is_item)
+            .map(|o| o.entity)
+    }
+
+    /// Tiles where more than one blocking entity stands, which should never happen
+    pub fn shared_blocker_tiles(&self) -> Vec<Point> {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| tile.iter().filter(|o| o.blocks).count() > 1)
+            .map(|(idx, _)| Point::new(idx as i32 % SCREEN_WIDTH, idx as i32 / SCREEN_WIDTH))
+            .collect()
+    }
+
+    /// Everything on a tile within the field of view, with its position
+    pub fn visible_entities<'a>(
+        &'a self,
+        fov: &'a FieldOfView,
+    ) -> impl Iterator<Item = (Point, Occupant)> + 'a {
+        fov.visible_tiles
+            .iter()
+            .flat_map(move |pos| self.entities_at(pos).iter().map(move |o| (pos, *o)))
+    }
+
+    fn tile(&self, pos: Point) -> Option<&[Occupant]> {
+        if pos.x >= 0 && pos.x < SCREEN_WIDTH && pos.y >= 0 && pos.y < SCREEN_HEIGHT {
+            Some(&self.tiles[map_idx(pos.x, pos.y)])
+        } else {
+            None
+        }
+    }
+
+    fn tile_mut(&mut self, pos: Point) -> Option<&mut Vec<Occupant>> {
+        if pos.x >= 0 && pos.x < SCREEN_WIDTH && pos.y >= 0 && pos.y < SCREEN_HEIGHT {
+            Some(&mut self.tiles[map_idx(pos.x, pos.y)])
+        } else {
+            None
+        }
+    }
+}
@@ -1,10 +1,12 @@
 mod template;
 
-use crate::{prelude::*, spawner::template::Templates};
+use crate::prelude::*;
+use std::collections::HashSet;
+pub use template::Templates;
 
 /// Spawns the player entity at the given position
 pub fn spawn_player(ecs: &mut World, pos: Point) {
-    ecs.push((
+    let player = ecs.push((
         Player { map_level: 0 },
         pos,
         Render {
@@ -16,8 +18,23 @@ pub fn spawn_player(ecs: &mut World, pos: Point) {
             max: 100,
         },
         FieldOfView::new(8),
-        Damage(1),
+        Damage { min: 1, max: 2 },
+        BlocksTile,
     ));
+
+    // Legion only takes so many components at once, so the rest are added afterwards
+    let mut player = ecs.entry(player).expect("Failed to find the player");
+    player.add_component(LightSource {
+        radius: 5,
+        color: RGB::from_u8(255, 230, 170),
+    });
+    player.add_component(Darkvision(2));
+    player.add_component(Experience::new());
+    player.add_component(Hunger::new());
+    player.add_component(Faction("Player".to_string()));
+    player.add_component(Energy::new(NORMAL_SPEED));
+    player.add_component(Regeneration(5));
+    player.add_component(Gold(0));
 }
 
 /// Spawns the Amulet of Yendor at the given position
@@ -34,12 +51,48 @@ pub fn spawn_amulet(ecs: &mut World, pos: Point) {
     ));
 }
 
+/// Spawns a pile of gold, like the one a monster drops when it is killed
+pub fn spawn_gold(commands: &mut CommandBuffer, pos: Point, amount: i32) {
+    commands.push((
+        Item,
+        Gold(amount),
+        pos,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('$'),
+        },
+        Name("Gold".to_string()),
+    ));
+}
+
+/// Spawns everything the map builder placed on the level
 pub fn spawn_level(
     ecs: &mut World,
     rng: &mut RandomNumberGenerator,
     map_level: usize,
-    spawn_points: &[Point],
+    map_builder: &MapBuilder,
 ) {
+    // Never put two spawns, or a spawn and the player, on the same tile
+    let mut occupied = HashSet::from([map_builder.player_start]);
+    let named_spawns: Vec<(Point, String)> = map_builder
+        .named_spawns
+        .iter()
+        .filter(|(pos, _)| occupied.insert(*pos))
+        .cloned()
+        .collect();
+    let spawn_points: Vec<Point> = map_builder
+        .monster_spawns
+        .iter()
+        .filter(|pos| occupied.insert(**pos))
+        .copied()
+        .collect();
+
     let template = Templates::load();
-    template.spawn_entities(ecs, rng, map_level, spawn_points);
+    template.spawn_entities(ecs, rng, map_level, &spawn_points);
+    template.spawn_named_entities(ecs, &named_spawns);
+    template.stock_shops(ecs, rng, map_level);
+
+    map_builder.decorations.iter().for_each(|(pos, render)| {
+        ecs.push((*pos, *render));
+    });
 }
@@ -12,15 +12,61 @@ pub struct Template {
     pub frequency: u32,
     pub name: String,
     pub glyph: char,
+    /// What the inventory screen says about the item
+    pub description: Option<String>,
+    /// What the item looks like until it is identified, if it needs identifying
+    pub appearance: Option<Appearance>,
     pub provides: Option<Vec<(String, i32)>>,
+    /// Status effects and their durations that the monster's hits cause
+    pub inflicts: Option<Vec<(String, i32)>>,
     pub hp: Option<i32>,
+    /// Experience awarded for killing the monster
+    pub xp: Option<i32>,
+    /// Gold in a pile, or that the monster drops when it is killed
+    pub gold: Option<i32>,
+    /// What the item costs in a shop
+    pub value: Option<i32>,
+    /// How many items the monster has for sale, if it keeps a shop
+    pub stock: Option<usize>,
+    /// The faction the monster belongs to, see `resources/factions.ron`
+    pub faction: Option<String>,
+    /// How the monster behaves, `Chase` if omitted
+    pub ai: Option<Ai>,
+    /// How far the monster can see
+    pub vision: Option<i32>,
+    /// How much energy the monster gains every turn, `NORMAL_SPEED` if omitted
+    pub speed: Option<i32>,
+    /// Turns the monster takes to heal a point of health, if it heals at all
+    pub regen: Option<i32>,
+    /// Whether the monster starts asleep, until a noise or a hit wakes it
+    pub asleep: Option<bool>,
+    /// How much louder the item makes its wearer's steps
+    pub noise: Option<i32>,
     pub base_damage: Option<i32>,
+    /// Upper end of the damage range, which is just `base_damage` if omitted
+    pub max_damage: Option<i32>,
+    pub accuracy: Option<i32>,
+    pub defense: Option<i32>,
+    pub armor: Option<i32>,
+    /// Where the item is equipped. Items with damage go in the main hand if omitted.
+    pub slot: Option<EquipmentSlot>,
+    pub health_bonus: Option<i32>,
+    pub sight_bonus: Option<i32>,
+    /// How far the item or monster can shoot, or a scroll can be aimed
+    pub range: Option<i32>,
+    /// Whether the item is thrown, landing where it hits
+    pub thrown: Option<bool>,
+    pub charges: Option<i32>,
+    /// Radius and color of the light the entity gives off
+    pub light: Option<(i32, (u8, u8, u8))>,
 }
 
 #[derive(Deserialize, Clone, Debug, PartialEq)]
 pub enum EntityType {
     Enemy,
     Item,
+    /// Scenery, like torches, that is placed by themes and levels instead of spawned randomly
+    Prop,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -67,7 +113,65 @@ impl Templates {
         commands.flush(ecs);
     }
 
-    fn spawn_entity(&self, pos: Point, template: &Template, commands: &mut CommandBuffer) {
+    /// Spawns entities of specific templates, regardless of the depth they usually appear at
+    pub fn spawn_named_entities(&self, ecs: &mut World, named_spawns: &[(Point, String)]) {
+        let mut commands = CommandBuffer::new(ecs);
+        named_spawns.iter().for_each(|(pos, name)| {
+            if let Some(template) = self.entities.iter().find(|e| e.name == *name) {
+                println!("Spawning entity {:?} at {:?}", template.name, pos);
+                self.spawn_entity(*pos, template, &mut commands);
+            } else {
+                println!("Unknown template: {}", name);
+            }
+        });
+        commands.flush(ecs);
+    }
+
+    /// Gives every shopkeeper on the level its stock, drawn from the items that can be
+    /// found at this depth
+    pub fn stock_shops(&self, ecs: &mut World, rng: &mut RandomNumberGenerator, map_level: usize) {
+        let mut for_sale = Vec::new();
+        self.entities
+            .iter()
+            .filter(|e| {
+                e.entity_type == EntityType::Item
+                    && e.value.is_some()
+                    && e.levels.contains(&map_level)
+            })
+            .for_each(|e| {
+                for _ in 0..e.frequency {
+                    for_sale.push(e.clone());
+                }
+            });
+
+        let shopkeepers: Vec<(Entity, usize)> = <(Entity, &Name)>::query()
+            .filter(component::<Shopkeeper>())
+            .iter(ecs)
+            .filter_map(|(entity, name)| {
+                let template = self.entities.iter().find(|e| e.name == name.0)?;
+                Some((*entity, template.stock.unwrap_or(0)))
+            })
+            .collect();
+
+        let mut commands = CommandBuffer::new(ecs);
+        shopkeepers.iter().for_each(|(shopkeeper, stock)| {
+            for _ in 0..*stock {
+                if let Some(template) = rng.random_slice_entry(&for_sale) {
+                    let item = self.spawn_entity(Point::zero(), template, &mut commands);
+                    commands.remove_component::<Point>(item);
+                    commands.add_component(item, Carried(*shopkeeper));
+                }
+            }
+        });
+        commands.flush(ecs);
+    }
+
+    fn spawn_entity(
+        &self,
+        pos: Point,
+        template: &Template,
+        commands: &mut CommandBuffer,
+    ) -> Entity {
         // Render the entity
         let entity = commands.push((
             pos,
@@ -78,13 +182,32 @@ impl Templates {
             Name(template.name.clone()),
         ));
 
+        if let Some(description) = &template.description {
+            commands.add_component(entity, Description(description.clone()));
+        }
+        if let Some(gold) = template.gold {
+            commands.add_component(entity, Gold(gold));
+        }
+        if let Some(value) = template.value {
+            commands.add_component(entity, Value(value));
+        }
+
         // Add components based on entity type
         match template.entity_type {
             EntityType::Item => commands.add_component(entity, Item {}),
             EntityType::Enemy => {
                 commands.add_component(entity, Enemy);
-                commands.add_component(entity, FieldOfView::new(6));
-                commands.add_component(entity, ChasingPlayer);
+                commands.add_component(entity, BlocksTile);
+                commands.add_component(entity, FieldOfView::new(template.vision.unwrap_or(6)));
+                let ai = template.ai.unwrap_or(Ai::Chase);
+                commands.add_component(entity, ai);
+                commands.add_component(entity, Energy::new(template.speed.unwrap_or(NORMAL_SPEED)));
+                if let Ai::Guard { .. } = ai {
+                    commands.add_component(entity, GuardPost(pos));
+                }
+                if template.asleep == Some(true) {
+                    commands.add_component(entity, Asleep { turns: i32::MAX });
+                }
                 commands.add_component(
                     entity,
                     Health {
@@ -92,11 +215,43 @@ impl Templates {
                         max: template.hp.unwrap_or(1),
                     },
                 );
+                if let Some(regen) = template.regen {
+                    commands.add_component(entity, Regeneration(regen));
+                }
+                if let Some(xp) = template.xp {
+                    commands.add_component(entity, XpValue(xp));
+                }
+                if template.stock.is_some() {
+                    commands.add_component(entity, Shopkeeper);
+                }
+                if let Some(faction) = &template.faction {
+                    commands.add_component(entity, Faction(faction.clone()));
+                }
             }
+            EntityType::Prop => {}
+        }
+
+        if let Some((radius, (r, g, b))) = template.light {
+            commands.add_component(
+                entity,
+                LightSource {
+                    radius,
+                    color: RGB::from_u8(r, g, b),
+                },
+            );
+            commands.add_component(entity, FieldOfView::new(radius));
         }
 
         // If the entity provides effects, add those components
         if let Some(effects) = &template.provides {
+            let mut statuses = Vec::new();
+            // Radius and jumps only modify the area and chain effects
+            let setting = |name: &str, default: i32| {
+                effects
+                    .iter()
+                    .find(|(effect, _)| effect == name)
+                    .map_or(default, |(_, value)| *value)
+            };
             for (effect, value) in effects {
                 match effect.as_str() {
                     "Healing" => {
@@ -105,18 +260,119 @@ impl Templates {
                     "DungeonMap" => {
                         commands.add_component(entity, ProvidesDungeonMap);
                     }
-                    _ => {
-                        println!("Unknown effect: {}", effect);
+                    "Nutrition" => {
+                        commands.add_component(entity, ProvidesNutrition(*value));
+                    }
+                    "AreaDamage" => {
+                        let radius = setting("Radius", 1);
+                        commands.add_component(
+                            entity,
+                            ProvidesAreaDamage {
+                                damage: *value,
+                                radius,
+                            },
+                        );
+                    }
+                    "ChainLightning" => {
+                        let jumps = setting("Jumps", 2);
+                        commands.add_component(
+                            entity,
+                            ProvidesChainLightning {
+                                damage: *value,
+                                jumps,
+                            },
+                        );
+                    }
+                    "MagicMissile" => {
+                        commands.add_component(entity, ProvidesMagicMissile { damage: *value });
                     }
+                    "Teleport" => {
+                        commands.add_component(entity, ProvidesTeleport);
+                    }
+                    "Charm" => {
+                        commands.add_component(entity, ProvidesCharm);
+                    }
+                    "Identify" => {
+                        commands.add_component(entity, ProvidesIdentify);
+                    }
+                    "Radius" | "Jumps" => {}
+                    _ => match StatusEffect::from_name(effect) {
+                        Some(status) => statuses.push((status, *value)),
+                        None => println!("Unknown effect: {}", effect),
+                    },
                 }
             }
+            if !statuses.is_empty() {
+                commands.add_component(entity, ProvidesStatus(statuses));
+            }
+        }
+
+        if let Some(effects) = &template.inflicts {
+            let statuses: Vec<(StatusEffect, i32)> = effects
+                .iter()
+                .filter_map(|(effect, turns)| match StatusEffect::from_name(effect) {
+                    Some(status) => Some((status, *turns)),
+                    None => {
+                        println!("Unknown effect: {}", effect);
+                        None
+                    }
+                })
+                .collect();
+            commands.add_component(entity, InflictsStatus(statuses));
         }
 
         if let Some(damage) = &template.base_damage {
-            commands.add_component(entity, Damage(*damage));
+            commands.add_component(
+                entity,
+                Damage {
+                    min: *damage,
+                    max: template.max_damage.unwrap_or(*damage).max(*damage),
+                },
+            );
             if template.entity_type == EntityType::Item {
                 commands.add_component(entity, Weapon);
             }
         }
+
+        // Melee weapons go in the main hand unless the template says otherwise
+        let slot = match (template.slot, &template.base_damage, template.range) {
+            (Some(slot), _, _) => Some(slot),
+            (None, Some(_), None) if template.entity_type == EntityType::Item => {
+                Some(EquipmentSlot::MainHand)
+            }
+            _ => None,
+        };
+        if let Some(slot) = slot {
+            commands.add_component(entity, Equippable(slot));
+        }
+        if let Some(bonus) = template.health_bonus {
+            commands.add_component(entity, HealthBonus(bonus));
+        }
+        if let Some(bonus) = template.sight_bonus {
+            commands.add_component(entity, SightBonus(bonus));
+        }
+        if let Some(noise) = template.noise {
+            commands.add_component(entity, NoiseModifier(noise));
+        }
+        if let Some(range) = template.range {
+            commands.add_component(entity, Ranged { range });
+        }
+        if template.thrown == Some(true) {
+            commands.add_component(entity, Thrown);
+        }
+        if let Some(charges) = template.charges {
+            commands.add_component(entity, Charges(charges));
+        }
+
+        if let Some(accuracy) = template.accuracy {
+            commands.add_component(entity, Accuracy(accuracy));
+        }
+        if let Some(defense) = template.defense {
+            commands.add_component(entity, Defense(defense));
+        }
+        if let Some(armor) = template.armor {
+            commands.add_component(entity, Armor(armor));
+        }
+        entity
     }
 }
@@ -1,13 +1,25 @@
-mod chasing;
+mod ai;
 mod combat;
 mod end_turn;
 mod entity_render;
+mod equipment;
+mod flow_fields;
 mod fov;
 mod hud;
+mod hunger;
+mod inventory;
+mod lighting;
 mod map_render;
 mod movement;
+mod noise;
 mod player_input;
-mod random_move;
+mod projectiles;
+mod regeneration;
+mod shop;
+mod spatial_index;
+mod status_effects;
+mod targeting;
+mod targeting_render;
 mod tooltips;
 mod use_items;
 
@@ -15,8 +27,10 @@ use crate::prelude::*;
 
 pub fn build_input_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
         .add_system(player_input::player_input_system())
         .add_system(fov::fov_system())
+        .add_system(lighting::lighting_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
@@ -25,14 +39,51 @@ pub fn build_input_scheduler() -> Schedule {
         .build()
 }
 
+pub fn build_targeting_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(targeting::targeting_system())
+        .flush()
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(hud::hud_system())
+        .add_system(targeting_render::targeting_render_system())
+        .build()
+}
+
+pub fn build_inventory_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(inventory::inventory_screen_system())
+        .build()
+}
+
+pub fn build_shop_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(shop::shop_screen_system())
+        .build()
+}
+
 pub fn build_player_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(projectiles::projectiles_system())
+        .flush()
         .add_system(use_items::use_items_system())
+        .add_system(equipment::equipment_system())
+        .flush()
         .add_system(combat::combat_system())
         .flush()
+        .add_system(hunger::hunger_system())
+        .add_system(regeneration::regeneration_system())
+        .add_system(status_effects::status_effects_system())
+        .flush()
         .add_system(movement::movement_system())
         .flush()
         .add_system(fov::fov_system())
+        .add_system(lighting::lighting_system())
+        .add_system(noise::noise_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
@@ -43,15 +94,25 @@ pub fn build_player_scheduler() -> Schedule {
 
 pub fn build_monster_scheduler() -> Schedule {
     Schedule::builder()
-        .add_system(random_move::random_move_system())
-        .add_system(chasing::chasing_system())
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(flow_fields::flow_fields_system())
+        .add_system(ai::ai_system())
+        .flush()
+        .add_system(projectiles::projectiles_system())
         .flush()
         .add_system(use_items::use_items_system())
+        .flush()
         .add_system(combat::combat_system())
         .flush()
+        .add_system(hunger::hunger_system())
+        .add_system(regeneration::regeneration_system())
+        .add_system(status_effects::status_effects_system())
+        .flush()
         .add_system(movement::movement_system())
         .flush()
         .add_system(fov::fov_system())
+        .add_system(lighting::lighting_system())
+        .add_system(noise::noise_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
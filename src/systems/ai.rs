@@ -0,0 +1,405 @@
+use crate::prelude::*;
+
+/// How many turns a monster keeps looking for a hostile it has lost sight of
+const PATIENCE: i32 = 10;
+
+/// What a monster decided to do this turn
+enum Action {
+    /// Step onto a tile, attacking whatever hostile stands there
+    Step(Point),
+    Shoot(Point),
+    Wait,
+}
+
+/// The hostile entity a monster has its eye on
+struct Target {
+    pos: Point,
+    is_player: bool,
+}
+
+/// Decides what every monster does this turn, according to its `Ai`
+#[system]
+#[read_component(Point)]
+#[read_component(Ai)]
+#[read_component(GuardPost)]
+#[read_component(LastSeen)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Player)]
+#[read_component(Asleep)]
+#[read_component(Energy)]
+#[read_component(Ranged)]
+#[read_component(Faction)]
+#[allow(clippy::too_many_arguments)]
+pub fn ai(
+    #[resource] map: &Map,
+    #[resource] flow_fields: &mut FlowFields,
+    #[resource] index: &SpatialIndex,
+    #[resource] factions: &FactionTable,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    let mut rng = RandomNumberGenerator::new();
+    // Who spotted a hostile this turn, from where, and where the hostile is
+    let mut sightings = Vec::<(Entity, Point, Point)>::new();
+
+    <(Entity, &Point, &Ai, &FieldOfView)>::query()
+        .iter(ecs)
+        .for_each(|(entity, pos, ai, fov)| {
+            let Ok(entry) = ecs.entry_ref(*entity) else {
+                return;
+            };
+            // Only monsters with the energy for it act, and sleeping ones just let time pass
+            if !entry.get_component::<Energy>().is_ok_and(|e| e.is_ready()) {
+                return;
+            }
+            if entry.get_component::<Asleep>().is_ok() {
+                spend_energy(ecs, commands, *entity, WAIT_COST);
+                return;
+            }
+
+            // Go after the closest hostile in sight, and do not chase anything unseen
+            let target = index
+                .visible_entities(fov)
+                .filter(|(_, o)| {
+                    o.blocks
+                        && o.entity != *entity
+                        && factions.reaction_between(ecs, *entity, o.entity) == Reaction::Hostile
+                })
+                .min_by(|(a, _), (b, _)| {
+                    let a = DistanceAlg::Pythagoras.distance2d(*pos, *a);
+                    let b = DistanceAlg::Pythagoras.distance2d(*pos, *b);
+                    a.total_cmp(&b)
+                })
+                .map(|(target_pos, o)| Target {
+                    pos: target_pos,
+                    is_player: ecs
+                        .entry_ref(o.entity)
+                        .is_ok_and(|e| e.get_component::<Player>().is_ok()),
+                });
+            let distance = |target: &Target| DistanceAlg::Pythagoras.distance2d(*pos, target.pos);
+            let memory = entry.get_component::<LastSeen>().ok().copied();
+
+            // Ambushers spring when a hostile comes close, or when an ally raises the alarm
+            let sprung = match *ai {
+                Ai::Ambush { radius } => {
+                    memory.is_some()
+                        || target
+                            .as_ref()
+                            .is_some_and(|target| distance(target) <= radius as f32)
+                }
+                _ => false,
+            };
+
+            // Guards stay at their post, and lurking ambushers do not give themselves away
+            let hunts = !matches!(*ai, Ai::Guard { .. } | Ai::Ambush { .. }) || sprung;
+            if let (Some(target), true) = (&target, hunts) {
+                commands.add_component(
+                    *entity,
+                    LastSeen {
+                        pos: target.pos,
+                        patience: PATIENCE,
+                        arrived: false,
+                    },
+                );
+                sightings.push((*entity, *pos, target.pos));
+            }
+
+            let chase = |flow_fields: &mut FlowFields, target: &Target| {
+                // Ranged attackers shoot when they have a clear shot, instead of closing in
+                if let Ok(ranged) = entry.get_component::<Ranged>() {
+                    let path = trace_projectile(map, index, *pos, target.pos);
+                    let clear_shot = path.tiles.last() == Some(&target.pos);
+                    if distance(target) > 1.5
+                        && distance(target) <= ranged.range as f32
+                        && clear_shot
+                    {
+                        return Action::Shoot(target.pos);
+                    }
+                }
+                if distance(target) < 1.2 {
+                    return Action::Step(target.pos);
+                }
+                step_towards(map, flow_fields, *pos, target).map_or(Action::Wait, Action::Step)
+            };
+
+            let action = match (*ai, &target) {
+                (Ai::Chase, Some(target)) => chase(flow_fields, target),
+                (Ai::Chase, None) => search(commands, &mut rng, map, index, *entity, *pos, memory)
+                    .unwrap_or_else(|| give_up(commands, &mut rng, *entity, *pos, memory)),
+                (Ai::Wander, Some(target)) => chase(flow_fields, target),
+                (Ai::Wander, None) => search(commands, &mut rng, map, index, *entity, *pos, memory)
+                    .unwrap_or_else(|| Action::Step(random_step(&mut rng, *pos))),
+                (Ai::Coward { flee_below }, Some(target)) => {
+                    let wounded = entry
+                        .get_component::<Health>()
+                        .is_ok_and(|health| health.current * 100 <= health.max * flee_below);
+                    if wounded {
+                        step_away(map, flow_fields, index, *pos, target)
+                            .map_or(Action::Wait, Action::Step)
+                    } else {
+                        chase(flow_fields, target)
+                    }
+                }
+                (Ai::Coward { .. }, None) => {
+                    search(commands, &mut rng, map, index, *entity, *pos, memory)
+                        .unwrap_or_else(|| Action::Step(random_step(&mut rng, *pos)))
+                }
+                (
+                    Ai::Skirmish {
+                        distance: keep_away,
+                    },
+                    Some(target),
+                ) => {
+                    // Back off when the target gets too close, or fight if cornered
+                    if distance(target) < keep_away as f32 {
+                        match step_away(map, flow_fields, index, *pos, target) {
+                            Some(step) => Action::Step(step),
+                            None if distance(target) < 1.2 => Action::Step(target.pos),
+                            None => chase(flow_fields, target),
+                        }
+                    } else {
+                        chase(flow_fields, target)
+                    }
+                }
+                (Ai::Skirmish { .. }, None) => {
+                    search(commands, &mut rng, map, index, *entity, *pos, memory)
+                        .unwrap_or_else(|| Action::Step(random_step(&mut rng, *pos)))
+                }
+                (Ai::Guard { radius }, _) => {
+                    let post = entry
+                        .get_component::<GuardPost>()
+                        .map_or(*pos, |post| post.0);
+                    match &target {
+                        Some(target)
+                            if DistanceAlg::Pythagoras.distance2d(post, target.pos)
+                                <= radius as f32 =>
+                        {
+                            chase(flow_fields, target)
+                        }
+                        _ if *pos != post => {
+                            path_step(map, *pos, post).map_or(Action::Wait, Action::Step)
+                        }
+                        _ => Action::Wait,
+                    }
+                }
+                (Ai::Ambush { .. }, _) if sprung => {
+                    // Once sprung, an ambush turns into a chase
+                    commands.add_component(*entity, Ai::Chase);
+                    match &target {
+                        Some(target) => chase(flow_fields, target),
+                        None => search(commands, &mut rng, map, index, *entity, *pos, memory)
+                            .unwrap_or_else(|| give_up(commands, &mut rng, *entity, *pos, memory)),
+                    }
+                }
+                (Ai::Ambush { .. }, _) => Action::Wait,
+            };
+
+            let cost = match action {
+                Action::Step(destination) => {
+                    step_or_attack(ecs, commands, index, factions, *entity, destination)
+                }
+                Action::Shoot(target) => {
+                    commands.push((
+                        (),
+                        WantsToShoot {
+                            shooter: *entity,
+                            weapon: None,
+                            target,
+                        },
+                    ));
+                    ATTACK_COST
+                }
+                Action::Wait => WAIT_COST,
+            };
+            spend_energy(ecs, commands, *entity, cost);
+        });
+
+    // Monsters that spot a hostile alert the allies that can see them
+    let spotters: Vec<Entity> = sightings.iter().map(|(spotter, ..)| *spotter).collect();
+    sightings
+        .iter()
+        .for_each(|(spotter, spotter_pos, target_pos)| {
+            <(Entity, &Ai, &FieldOfView)>::query()
+                .iter(ecs)
+                .filter(|(ally, ai, fov)| {
+                    !spotters.contains(ally)
+                        && !matches!(ai, Ai::Guard { .. })
+                        && fov.visible_tiles.contains(spotter_pos)
+                        && factions.reaction_between(ecs, **ally, *spotter) == Reaction::Friendly
+                })
+                .for_each(|(ally, ..)| {
+                    commands.add_component(
+                        *ally,
+                        LastSeen {
+                            pos: *target_pos,
+                            patience: PATIENCE,
+                            arrived: false,
+                        },
+                    );
+                });
+        });
+}
+
+/// Head for where a lost hostile was last seen and look around there, until patience
+/// runs out. Returns `None` once the monster has given up.
+///
+/// Patience only runs down while looking around, or when the way there is missing or
+/// blocked, so a long walk does not use it up but something that never moves off the
+/// spot cannot hold the monster there forever.
+fn search(
+    commands: &mut CommandBuffer,
+    rng: &mut RandomNumberGenerator,
+    map: &Map,
+    index: &SpatialIndex,
+    entity: Entity,
+    pos: Point,
+    memory: Option<LastSeen>,
+) -> Option<Action> {
+    let memory = memory?;
+    if memory.patience < 1 {
+        commands.remove_component::<LastSeen>(entity);
+        return None;
+    }
+
+    let arrived = memory.arrived || pos == memory.pos;
+    let path = if arrived {
+        None
+    } else {
+        path_step(map, pos, memory.pos)
+    };
+    let blocked = path.is_some_and(|step| {
+        index
+            .blocker_at(step)
+            .is_some_and(|blocker| blocker != entity)
+    });
+    commands.add_component(
+        entity,
+        LastSeen {
+            patience: memory.patience - i32::from(path.is_none() || blocked),
+            arrived,
+            ..memory
+        },
+    );
+
+    Some(Action::Step(path.unwrap_or_else(|| random_step(rng, pos))))
+}
+
+/// What a chaser does without anything to chase or search for. One that has given up a
+/// search goes back to wandering, while one that never had a quarry keeps still.
+fn give_up(
+    commands: &mut CommandBuffer,
+    rng: &mut RandomNumberGenerator,
+    entity: Entity,
+    pos: Point,
+    memory: Option<LastSeen>,
+) -> Action {
+    if memory.is_none() {
+        return Action::Wait;
+    }
+    commands.add_component(entity, Ai::Wander);
+    Action::Step(random_step(rng, pos))
+}
+
+/// Move onto a tile, or attack what is standing there if it is hostile. Returns the
+/// energy it cost.
+fn step_or_attack(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    index: &SpatialIndex,
+    factions: &FactionTable,
+    entity: Entity,
+    destination: Point,
+) -> i32 {
+    match index.blocker_at(destination) {
+        Some(victim) if factions.reaction_between(ecs, entity, victim) == Reaction::Hostile => {
+            commands.push((
+                (),
+                WantsToAttack {
+                    attacker: entity,
+                    victim,
+                    stats: None,
+                },
+            ));
+            ATTACK_COST
+        }
+        // Bumping into a friend wastes the move
+        Some(_) => MOVE_COST,
+        None => {
+            commands.push((
+                (),
+                WantsToMove {
+                    entity,
+                    destination,
+                    teleport: false,
+                },
+            ));
+            MOVE_COST
+        }
+    }
+}
+
+fn random_step(rng: &mut RandomNumberGenerator, pos: Point) -> Point {
+    pos + match rng.range(0, 4) {
+        0 => Point::new(-1, 0),
+        1 => Point::new(1, 0),
+        2 => Point::new(0, -1),
+        _ => Point::new(0, 1),
+    }
+}
+
+/// The next tile on the shortest path between two tiles
+fn path_step(map: &Map, from: Point, to: Point) -> Option<Point> {
+    let path = a_star_search(map.point2d_to_index(from), map.point2d_to_index(to), map);
+    path.steps.get(1).map(|idx| map.index_to_point2d(*idx))
+}
+
+fn step_towards(
+    map: &Map,
+    flow_fields: &mut FlowFields,
+    pos: Point,
+    target: &Target,
+) -> Option<Point> {
+    if target.is_player {
+        flow_fields.next_step(FlowField::ToPlayer, pos, map)
+    } else {
+        // Everyone chases the player, so only the player gets a flow field
+        path_step(map, pos, target.pos)
+    }
+}
+
+/// A free neighbouring tile that leads away from the target
+fn step_away(
+    map: &Map,
+    flow_fields: &mut FlowFields,
+    index: &SpatialIndex,
+    pos: Point,
+    target: &Target,
+) -> Option<Point> {
+    let step = if target.is_player {
+        flow_fields.next_step(FlowField::Flee, pos, map)
+    } else {
+        // Without a flee map, just take the free tile furthest from the target
+        [
+            Point::new(-1, 0),
+            Point::new(1, 0),
+            Point::new(0, -1),
+            Point::new(0, 1),
+        ]
+        .into_iter()
+        .map(|delta| pos + delta)
+        .filter(|step| map.can_enter_tile(*step))
+        .max_by(|a, b| {
+            let a = DistanceAlg::Pythagoras.distance2d(*a, target.pos);
+            let b = DistanceAlg::Pythagoras.distance2d(*b, target.pos);
+            a.total_cmp(&b)
+        })
+    };
+
+    // Only worth it if the step actually leads away, onto a free tile
+    step.filter(|step| {
+        DistanceAlg::Pythagoras.distance2d(*step, target.pos)
+            > DistanceAlg::Pythagoras.distance2d(pos, target.pos)
+            && index.blocker_at(*step).is_none()
+    })
+}
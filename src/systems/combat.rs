@@ -5,51 +5,103 @@ use crate::prelude::*;
 #[read_component(Player)]
 #[write_component(Health)]
 #[read_component(Damage)]
+#[read_component(Equipped)]
+#[read_component(Accuracy)]
+#[read_component(Defense)]
+#[read_component(Armor)]
+#[read_component(InflictsStatus)]
+#[read_component(Asleep)]
+#[read_component(XpValue)]
+#[read_component(Gold)]
+#[read_component(Point)]
 #[read_component(Carried)]
+#[read_component(Shopkeeper)]
+#[write_component(Experience)]
 pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
     let mut attackers = <(Entity, &WantsToAttack)>::query();
-    let victims: Vec<(Entity, Entity, Entity)> = attackers
+    let victims: Vec<(Entity, Entity, Entity, Option<AttackStats>)> = attackers
         .iter(ecs)
-        .map(|(entity, attack)| (*entity, attack.attacker, attack.victim))
+        .map(|(entity, attack)| (*entity, attack.attacker, attack.victim, attack.stats))
         .collect();
 
-    victims.iter().for_each(|(message, attacker, victim)| {
-        let base_damage = if let Ok(attacker_entry) = ecs.entry_ref(*attacker) {
-            if let Ok(damage) = attacker_entry.get_component::<Damage>() {
-                damage.0
-            } else {
-                0
+    let mut rng = RandomNumberGenerator::new();
+    victims
+        .iter()
+        .for_each(|(message, attacker, victim, stats)| {
+            commands.remove(*message);
+
+            // Fighting is loud, whether or not the blow lands
+            if let Some(pos) = ecs
+                .entry_ref(*victim)
+                .ok()
+                .and_then(|entry| entry.get_component::<Point>().ok().copied())
+            {
+                commands.push((
+                    (),
+                    Noise {
+                        source: *attacker,
+                        pos,
+                        volume: COMBAT_NOISE,
+                    },
+                ));
+            }
+
+            // A shopkeeper the player turns on stops trading and fights back
+            let provoked = ecs
+                .entry_ref(*victim)
+                .is_ok_and(|victim| victim.get_component::<Shopkeeper>().is_ok())
+                && ecs
+                    .entry_ref(*attacker)
+                    .is_ok_and(|attacker| attacker.get_component::<Player>().is_ok());
+            if provoked {
+                commands.add_component(*victim, Faction(ANGRY_SHOPKEEPER.to_string()));
+                commands.add_component(*victim, Ai::Chase);
+            }
+
+            let attack = stats.unwrap_or_else(|| AttackStats::of(ecs, *attacker));
+            let defense = DefenseStats::of(ecs, *victim);
+            let AttackOutcome::Hit { damage } = resolve_attack(&mut rng, &attack, &defense) else {
+                return;
+            };
+
+            let Ok(victim_entry) = ecs.entry_ref(*victim) else {
+                return;
+            };
+            let is_player = victim_entry.get_component::<Player>().is_ok();
+            let is_asleep = victim_entry.get_component::<Asleep>().is_ok();
+
+            let mut is_dead = false;
+            if let Ok(health) = ecs
+                .entry_mut(*victim)
+                .unwrap()
+                .get_component_mut::<Health>()
+            {
+                // A monster killed earlier this turn is already on its way out
+                if health.current < 1 && !is_player {
+                    return;
+                }
+                health.current -= damage;
+
+                is_dead = health.current < 1 && !is_player;
+            }
+            if is_dead {
+                kill(ecs, commands, *victim, Some(*attacker));
+                return;
             }
-        } else {
-            0
-        };
-
-        let weapon_damage = <(&Carried, &Damage)>::query()
-            .filter(component::<Player>())
-            .iter(ecs)
-            .filter(|(carried, _)| carried.0 == *attacker)
-            .map(|(_, damage)| damage.0)
-            .sum::<i32>();
-
-        let final_damage = base_damage + weapon_damage;
-
-        let is_player = ecs
-            .entry_ref(*victim)
-            .unwrap()
-            .get_component::<Player>()
-            .is_ok();
-
-        if let Ok(health) = ecs
-            .entry_mut(*victim)
-            .unwrap()
-            .get_component_mut::<Health>()
-        {
-            health.current -= final_damage;
-
-            if health.current < 1 && !is_player {
-                commands.remove(*victim);
+
+            // Getting hit wakes you up
+            if is_asleep {
+                commands.remove_component::<Asleep>(*victim);
+            }
+
+            if let Some(statuses) = ecs
+                .entry_ref(*attacker)
+                .ok()
+                .and_then(|entry| entry.get_component::<InflictsStatus>().ok().cloned())
+            {
+                statuses.0.iter().for_each(|(status, turns)| {
+                    status.apply(commands, *victim, *turns, *attacker);
+                });
             }
-        }
-        commands.remove(*message);
-    });
+        });
 }
@@ -5,7 +5,23 @@ use crate::prelude::*;
 #[read_component(Health)]
 #[read_component(Point)]
 #[read_component(AmuletOfYendor)]
-pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resource] map: &Map) {
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[write_component(Energy)]
+#[read_component(Experience)]
+pub fn end_turn(
+    ecs: &mut SubWorld,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] clock: &mut Clock,
+    #[resource] map: &Map,
+) {
+    let current_state = *turn_state;
+    let mut new_state = match current_state {
+        TurnState::AwaitingInput => return,
+        TurnState::PlayerTurn | TurnState::MonsterTurn => next_turn(ecs, clock),
+        _ => current_state,
+    };
+
     let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
 
     // Some stages may not have the amulet, but stairs instead
@@ -17,13 +33,14 @@ pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resour
         .next()
         .unwrap_or(&amulet_default);
 
-    let current_state = *turn_state;
-    let mut new_state = match current_state {
-        TurnState::AwaitingInput => return,
-        TurnState::PlayerTurn => TurnState::MonsterTurn,
-        TurnState::MonsterTurn => TurnState::AwaitingInput,
-        _ => current_state,
-    };
+    // Levelling up waits until the monsters are done, so the player is not caught mid-turn
+    let can_level_up = <&Experience>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .any(|experience| experience.can_level_up());
+    if new_state == TurnState::AwaitingInput && can_level_up {
+        new_state = TurnState::LevelUp;
+    }
 
     player_hp.iter(ecs).for_each(|(hp, pos)| {
         if hp.current < 1 {
@@ -42,3 +59,153 @@ pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resour
 
     *turn_state = new_state;
 }
+
+/// Lets time pass until somebody has the energy to act, and says whose turn it is
+///
+/// The player goes first when they and monsters are ready at the same time.
+fn next_turn(ecs: &mut SubWorld, clock: &mut Clock) -> TurnState {
+    loop {
+        let mut actors = <(&Energy, Option<&Player>)>::query();
+        if actors
+            .iter(ecs)
+            .any(|(energy, player)| player.is_some() && energy.is_ready())
+        {
+            return TurnState::AwaitingInput;
+        }
+        if actors.iter(ecs).any(|(energy, _)| energy.is_ready()) {
+            return TurnState::MonsterTurn;
+        }
+
+        // Nobody can act yet, so a turn passes and everyone gains energy
+        let mut time_passes = false;
+        <(&mut Energy, Option<&Hasted>, Option<&Slowed>)>::query().for_each_mut(
+            ecs,
+            |(energy, hasted, slowed)| {
+                let gain = energy.gain(hasted.is_some(), slowed.is_some());
+                energy.current += gain;
+                time_passes |= gain > 0;
+            },
+        );
+        if !time_passes {
+            return TurnState::AwaitingInput;
+        }
+        clock.turn += 1;
+        clock.pending_turns += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[system]
+    #[read_component(Player)]
+    #[read_component(Hasted)]
+    #[read_component(Slowed)]
+    #[write_component(Energy)]
+    fn schedule(
+        ecs: &mut SubWorld,
+        #[resource] clock: &mut Clock,
+        #[resource] turn_state: &mut TurnState,
+    ) {
+        *turn_state = next_turn(ecs, clock);
+    }
+
+    struct Game {
+        world: World,
+        resources: Resources,
+        schedule: Schedule,
+    }
+
+    impl Game {
+        fn new() -> Self {
+            let mut resources = Resources::default();
+            resources.insert(Clock::new());
+            resources.insert(TurnState::AwaitingInput);
+            Self {
+                world: World::default(),
+                resources,
+                schedule: Schedule::builder().add_system(schedule_system()).build(),
+            }
+        }
+
+        fn next_turn(&mut self) -> TurnState {
+            self.schedule.execute(&mut self.world, &mut self.resources);
+            *self.resources.get::<TurnState>().unwrap()
+        }
+
+        fn energy(&self, entity: Entity) -> i32 {
+            self.world
+                .entry_ref(entity)
+                .unwrap()
+                .get_component::<Energy>()
+                .unwrap()
+                .current
+        }
+
+        fn spend(&mut self, entity: Entity) {
+            let mut entry = self.world.entry(entity).unwrap();
+            entry.get_component_mut::<Energy>().unwrap().current -= MOVE_COST;
+        }
+
+        fn turn(&self) -> i32 {
+            self.resources.get::<Clock>().unwrap().turn
+        }
+    }
+
+    fn energy(speed: i32, current: i32) -> Energy {
+        Energy { speed, current }
+    }
+
+    #[test]
+    fn the_player_goes_first_on_a_tie() {
+        let mut game = Game::new();
+        let player = game
+            .world
+            .push((Player { map_level: 0 }, energy(NORMAL_SPEED, 0)));
+        let monster = game.world.push((energy(NORMAL_SPEED, 0),));
+
+        assert_eq!(game.next_turn(), TurnState::AwaitingInput);
+        assert_eq!(game.turn(), 1);
+        game.spend(player);
+
+        assert_eq!(game.next_turn(), TurnState::MonsterTurn);
+        assert_eq!(game.turn(), 1);
+        assert_eq!(game.energy(monster), ENERGY_TO_ACT);
+    }
+
+    #[test]
+    fn a_fast_monster_acts_twice_a_turn() {
+        let mut game = Game::new();
+        let player = game
+            .world
+            .push((Player { map_level: 0 }, energy(NORMAL_SPEED, 0)));
+        let bat = game.world.push((energy(NORMAL_SPEED * 2, 0),));
+
+        let (mut player_acts, mut bat_acts) = (0, 0);
+        for _ in 0..30 {
+            match game.next_turn() {
+                TurnState::AwaitingInput => {
+                    player_acts += 1;
+                    game.spend(player);
+                }
+                TurnState::MonsterTurn => {
+                    bat_acts += 1;
+                    game.spend(bat);
+                }
+                state => panic!("Unexpected turn state {:?}", state),
+            }
+        }
+        assert_eq!(game.turn(), 10);
+        assert_eq!(player_acts, 10);
+        assert_eq!(bat_acts, 20);
+    }
+
+    #[test]
+    fn time_stands_still_when_nobody_gains_energy() {
+        let mut game = Game::new();
+        game.world.push((energy(0, 0),));
+        assert_eq!(game.next_turn(), TurnState::AwaitingInput);
+        assert_eq!(game.turn(), 0);
+    }
+}
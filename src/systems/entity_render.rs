@@ -5,8 +5,11 @@ use crate::prelude::*;
 #[read_component(Render)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
-    let mut renderables = <(&Point, &Render)>::query();
+pub fn entity_render(
+    ecs: &SubWorld,
+    #[resource] camera: &Camera,
+    #[resource] index: &SpatialIndex,
+) {
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
     let player_fov = fov.iter(ecs).next().unwrap();
 
@@ -14,11 +17,18 @@ pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
     draw_batch.target(ENTITY_CONSOLE_ID);
     let offset = Point::new(camera.left_x, camera.top_y);
 
-    renderables
-        .iter(ecs)
-        .filter(|(pos, _)| player_fov.visible_tiles.contains(pos))
-        .for_each(|(pos, render)| {
-            draw_batch.set(*pos - offset, render.color, render.glyph);
+    // Draw whatever lies on the floor first, so that creatures are drawn on top of it
+    let mut visible: Vec<(Point, Occupant)> = index.visible_entities(player_fov).collect();
+    visible.sort_by_key(|(_, occupant)| occupant.blocks);
+
+    // Entities removed earlier this turn may still be in the index, so skip those
+    visible
+        .iter()
+        .filter_map(|(pos, occupant)| Some((pos, ecs.entry_ref(occupant.entity).ok()?)))
+        .for_each(|(pos, entry)| {
+            if let Ok(render) = entry.get_component::<Render>() {
+                draw_batch.set(*pos - offset, render.color, render.glyph);
+            }
         });
 
     // Submit the batch on later order to ensure other more important batches are drawn first
@@ -0,0 +1,112 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(EquipItem)]
+#[read_component(UnequipItem)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(HealthBonus)]
+#[read_component(SightBonus)]
+#[write_component(Health)]
+#[write_component(FieldOfView)]
+pub fn equipment(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+    // What is equipped right now. Kept up to date here, since the command buffer
+    // only adds and removes `Equipped` components at the end of the schedule.
+    let mut equipped: Vec<(Entity, Equipped)> = <(Entity, &Equipped)>::query()
+        .iter(ecs)
+        .map(|(item, equipped)| (*item, *equipped))
+        .collect();
+
+    let mut requests: Vec<(Entity, Entity, bool)> = Vec::new();
+    <(Entity, &EquipItem)>::query()
+        .iter(ecs)
+        .for_each(|(message, equip)| {
+            commands.remove(*message);
+            requests.push((equip.owner, equip.item, true));
+        });
+    <(Entity, &UnequipItem)>::query()
+        .iter(ecs)
+        .for_each(|(message, unequip)| {
+            commands.remove(*message);
+            requests.push((unequip.owner, unequip.item, false));
+        });
+
+    for (owner, item, equip) in requests {
+        let is_equipped = equipped.iter().any(|(e, _)| *e == item);
+
+        if !equip {
+            if is_equipped {
+                unequip_item(ecs, commands, &mut equipped, item);
+            }
+            continue;
+        }
+
+        let slot = match ecs
+            .entry_ref(item)
+            .ok()
+            .and_then(|entry| entry.get_component::<Equippable>().ok().copied())
+        {
+            Some(Equippable(slot)) if !is_equipped => slot,
+            _ => continue,
+        };
+
+        // Whatever was in the slot goes back into the inventory
+        let replaced: Vec<Entity> = equipped
+            .iter()
+            .filter(|(_, e)| e.owner == owner && e.slot == slot)
+            .map(|(e, _)| *e)
+            .collect();
+        replaced
+            .into_iter()
+            .for_each(|other| unequip_item(ecs, commands, &mut equipped, other));
+
+        let new_equipped = Equipped { owner, slot };
+        commands.add_component(item, new_equipped);
+        equipped.push((item, new_equipped));
+        apply_bonuses(ecs, owner, item, 1);
+    }
+}
+
+fn unequip_item(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    equipped: &mut Vec<(Entity, Equipped)>,
+    item: Entity,
+) {
+    if let Some(idx) = equipped.iter().position(|(e, _)| *e == item) {
+        let (_, old) = equipped.remove(idx);
+        commands.remove_component::<Equipped>(item);
+        apply_bonuses(ecs, old.owner, item, -1);
+    }
+}
+
+/// Add (`sign` 1) or remove (`sign` -1) an item's health and sight bonuses to its owner
+fn apply_bonuses(ecs: &mut SubWorld, owner: Entity, item: Entity, sign: i32) {
+    let (health_bonus, sight_bonus) = match ecs.entry_ref(item) {
+        Ok(entry) => (
+            entry.get_component::<HealthBonus>().map_or(0, |b| b.0),
+            entry.get_component::<SightBonus>().map_or(0, |b| b.0),
+        ),
+        Err(_) => return,
+    };
+
+    let Ok(mut owner) = ecs.entry_mut(owner) else {
+        return;
+    };
+
+    if health_bonus != 0 {
+        if let Ok(health) = owner.get_component_mut::<Health>() {
+            health.max += sign * health_bonus;
+            // Putting the item on only raises the maximum, and taking it off trims
+            // health down to the new maximum, so swapping it cannot heal or kill
+            health.current = health.current.min(health.max);
+        }
+    }
+
+    if sight_bonus != 0 {
+        if let Ok(fov) = owner.get_component_mut::<FieldOfView>() {
+            fov.radius += sign * sight_bonus;
+            fov.is_dirty = true;
+        }
+    }
+}
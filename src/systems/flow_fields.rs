@@ -0,0 +1,27 @@
+use crate::prelude::*;
+
+/// Keeps the shared flow fields in sync with the player and item positions
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Item)]
+pub fn flow_fields(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] flow_fields: &mut FlowFields,
+) {
+    let player_pos = <&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .copied()
+        .unwrap_or(Point::zero());
+
+    let item_tiles = <&Point>::query()
+        .filter(component::<Item>())
+        .iter(ecs)
+        .map(|pos| map.point2d_to_index(*pos))
+        .collect();
+
+    flow_fields.update(player_pos, item_tiles);
+}
@@ -8,7 +8,9 @@ pub fn fov(ecs: &mut SubWorld, #[resource] map: &Map) {
         .iter_mut(ecs)
         .filter(|(_, fov)| fov.is_dirty)
         .for_each(|(pos, fov)| {
-            fov.visible_tiles = field_of_view_set(*pos, fov.radius, map);
+            fov.line_of_sight = field_of_view(*pos, fov.radius, map).into_iter().collect();
+            // The lighting system narrows this down for entities that need light to see
+            fov.visible_tiles = fov.line_of_sight.clone();
             fov.is_dirty = false;
         });
 }
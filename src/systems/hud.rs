@@ -7,7 +7,20 @@ use crate::prelude::*;
 #[read_component(Item)]
 #[read_component(Carried)]
 #[read_component(Name)]
-pub fn hud(ecs: &SubWorld) {
+#[read_component(Equipped)]
+#[read_component(Equippable)]
+#[read_component(Poisoned)]
+#[read_component(Confused)]
+#[read_component(Asleep)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[read_component(Charges)]
+#[read_component(Experience)]
+#[read_component(Hunger)]
+#[read_component(Sneaking)]
+#[read_component(Resting)]
+#[read_component(Gold)]
+pub fn hud(ecs: &SubWorld, #[resource] identification: &Identification) {
     let player_health = <&Health>::query()
         .filter(component::<Player>())
         .iter(ecs)
@@ -19,7 +32,10 @@ pub fn hud(ecs: &SubWorld) {
     draw_batch.target(UI_CONSOLE_ID);
 
     draw_batch
-        .print_centered(1, "Explore the Dungeon. Cursor keys to move.")
+        .print_centered(
+            1,
+            "Explore the Dungeon. Cursor keys to move, Space to wait, R to rest, C to sneak, I for items.",
+        )
         .bar_horizontal(
             Point::zero(),
             SCREEN_WIDTH * 2,
@@ -44,24 +60,107 @@ pub fn hud(ecs: &SubWorld) {
         format!("Dungeon Level: {}", map_level + 1),
         ColorPair::new(YELLOW, BLACK),
     );
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH * 2 - 18, 1),
+        format!("Gold: {}", gold_of(ecs, player)),
+        ColorPair::new(GOLD, BLACK),
+    );
 
-    let mut item_query = <(&Item, &Name, &Carried)>::query();
-    let mut y = 3;
-
-    item_query
+    if let Some(experience) = <&Experience>::query()
+        .filter(component::<Player>())
         .iter(ecs)
-        .filter(|(_, _, carried)| carried.0 == player)
-        .for_each(|(_, name, _)| {
-            draw_batch.print(Point::new(3, y), format!("{} : {}", y - 2, name.0));
+        .next()
+    {
+        draw_batch.print_color(
+            Point::new(0, 1),
+            format!(
+                "Level {}  XP: {} / {}",
+                experience.level,
+                experience.xp,
+                experience.next_level_xp()
+            ),
+            ColorPair::new(GOLD, BLACK),
+        );
+    }
+
+    // Show the player's status effects, with the turns they have left
+    if let Ok(entry) = ecs.entry_ref(player) {
+        let effects: Vec<(String, (u8, u8, u8))> = [
+            entry
+                .get_component::<Poisoned>()
+                .ok()
+                .map(|e| (format!("Poisoned ({})", e.turns), GREEN)),
+            entry
+                .get_component::<Confused>()
+                .ok()
+                .map(|e| (format!("Confused ({})", e.turns), MAGENTA)),
+            entry
+                .get_component::<Asleep>()
+                .ok()
+                .map(|e| (format!("Asleep ({})", e.turns), LIGHT_BLUE)),
+            entry
+                .get_component::<Hasted>()
+                .ok()
+                .map(|e| (format!("Hasted ({})", e.turns), CYAN)),
+            entry
+                .get_component::<Slowed>()
+                .ok()
+                .map(|e| (format!("Slowed ({})", e.turns), BROWN1)),
+            entry
+                .get_component::<Sneaking>()
+                .ok()
+                .map(|_| ("Sneaking".to_string(), GRAY)),
+            entry
+                .get_component::<Resting>()
+                .ok()
+                .map(|_| ("Resting".to_string(), GRAY)),
+            entry.get_component::<Hunger>().ok().and_then(|hunger| {
+                let state = hunger.state();
+                let color = match state {
+                    HungerState::WellFed => GREEN,
+                    HungerState::Hungry => ORANGE,
+                    _ => RED,
+                };
+                state.name().map(|name| (name.to_string(), color))
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let mut y = 2;
+        effects.iter().for_each(|(text, color)| {
+            draw_batch.print_color_right(
+                Point::new(SCREEN_WIDTH * 2, y),
+                text,
+                ColorPair::new(*color, BLACK),
+            );
             y += 1;
         });
+    }
 
-    if y > 3 {
+    // Only the slots with a number key fit here, the rest are on the inventory screen
+    let slots = inventory_slots(ecs, player);
+    let mut y = 3;
+    slots.iter().take(8).for_each(|slot| {
+        draw_batch.print(
+            Point::new(3, y),
+            format!("{} : {}", y - 2, slot.label(ecs, identification)),
+        );
+        y += 1;
+    });
+
+    if !slots.is_empty() {
         draw_batch.print_color(
             Point::new(3, 2),
             "Items carried",
             ColorPair::new(YELLOW, BLACK),
         );
+        draw_batch.print_color(
+            Point::new(3, y),
+            format!("I : all items ({} / {})", slots.len(), INVENTORY_CAPACITY),
+            ColorPair::new(GRAY, BLACK),
+        );
     }
 
     draw_batch.submit(10000).expect("Batch error");
@@ -0,0 +1,21 @@
+use crate::prelude::*;
+
+/// Makes everything that gets hungry a turn hungrier for every turn that has passed on
+/// the `Clock`, and hurts those that are starving
+///
+/// Hunger goes by game time, so a hasted player does not starve any faster. Runs
+/// before `regeneration` and `status_effects`, which marks those turns as done.
+#[system]
+#[write_component(Hunger)]
+#[write_component(Health)]
+pub fn hunger(ecs: &mut SubWorld, #[resource] clock: &Clock) {
+    if clock.pending_turns < 1 {
+        return;
+    }
+
+    <(&mut Hunger, &mut Health)>::query().for_each_mut(ecs, |(hunger, health)| {
+        for _ in 0..clock.pending_turns {
+            health.current -= hunger.tick();
+        }
+    });
+}
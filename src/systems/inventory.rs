@@ -0,0 +1,144 @@
+use crate::prelude::*;
+
+/// Shows the inventory screen, and uses, equips or drops the selected item
+///
+/// Using or dropping an item takes up the turn, except for items that need a target,
+/// which go on to `TurnState::Targeting` first.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Item)]
+#[read_component(Carried)]
+#[read_component(Name)]
+#[read_component(Description)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(Charges)]
+#[read_component(Ranged)]
+#[read_component(FieldOfView)]
+#[read_component(Faction)]
+#[read_component(Energy)]
+#[allow(clippy::too_many_arguments)]
+pub fn inventory_screen(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] mouse_pos: &Point,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] menu: &mut InventoryMenu,
+    #[resource] targeting: &mut Targeting,
+    #[resource] index: &SpatialIndex,
+    #[resource] factions: &FactionTable,
+    #[resource] identification: &Identification,
+) {
+    let Some((player, player_pos)) = <(Entity, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+    else {
+        return;
+    };
+
+    let slots = inventory_slots(ecs, player);
+    menu.selected = usize::min(menu.selected, slots.len().saturating_sub(1));
+    let selected = slots.get(menu.selected).map(|slot| slot.item());
+
+    if let Some(key) = key {
+        match (key, selected) {
+            (VirtualKeyCode::Escape | VirtualKeyCode::I, _) => {
+                *turn_state = TurnState::AwaitingInput;
+                return;
+            }
+            (VirtualKeyCode::Up | VirtualKeyCode::W, _) => {
+                menu.selected = menu.selected.saturating_sub(1);
+            }
+            (VirtualKeyCode::Down | VirtualKeyCode::S, _) => {
+                menu.selected = usize::min(menu.selected + 1, slots.len().saturating_sub(1));
+            }
+            (VirtualKeyCode::Return | VirtualKeyCode::U, Some(item)) => {
+                if let Some(range) = throw_range(ecs, item) {
+                    let cursor = nearest_target(ecs, index, factions, range);
+                    targeting.start(item, range, cursor, *mouse_pos);
+                    *turn_state = TurnState::Targeting;
+                } else {
+                    use_item(ecs, commands, player, item);
+                    spend_energy(ecs, commands, player, ITEM_COST);
+                    *turn_state = TurnState::PlayerTurn;
+                }
+                return;
+            }
+            (VirtualKeyCode::E, Some(item)) => {
+                let equippable = ecs
+                    .entry_ref(item)
+                    .is_ok_and(|e| e.get_component::<Equippable>().is_ok());
+                if equippable {
+                    use_item(ecs, commands, player, item);
+                    spend_energy(ecs, commands, player, ITEM_COST);
+                    *turn_state = TurnState::PlayerTurn;
+                    return;
+                }
+            }
+            (VirtualKeyCode::D, Some(item)) => {
+                drop_item(ecs, commands, player, item, player_pos);
+                spend_energy(ecs, commands, player, ITEM_COST);
+                *turn_state = TurnState::PlayerTurn;
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(UI_CONSOLE_ID);
+    draw_batch.print_color_centered(
+        2,
+        format!("Inventory ({} / {})", slots.len(), INVENTORY_CAPACITY),
+        ColorPair::new(GOLD, BLACK),
+    );
+
+    if slots.is_empty() {
+        draw_batch.print_centered(6, "You are not carrying anything.");
+    }
+    slots.iter().enumerate().for_each(|(i, slot)| {
+        let color = if i == menu.selected { YELLOW } else { WHITE };
+        let cursor = if i == menu.selected { ">" } else { " " };
+        draw_batch.print_color(
+            Point::new(10, 6 + i as i32),
+            format!(
+                "{} {:2} : {}",
+                cursor,
+                i + 1,
+                slot.label(ecs, identification)
+            ),
+            ColorPair::new(color, BLACK),
+        );
+    });
+
+    // Unidentified items do not give away what they are
+    let description = slots.get(menu.selected).and_then(|slot| {
+        match identification.unidentified_description(&slot.name) {
+            Some(description) => Some(description.to_string()),
+            None => ecs.entry_ref(slot.item()).ok().and_then(|entry| {
+                entry
+                    .get_component::<Description>()
+                    .ok()
+                    .map(|d| d.0.clone())
+            }),
+        }
+    });
+    if let Some(description) = description {
+        draw_batch.print_color(
+            Point::new(10, 8 + INVENTORY_CAPACITY as i32),
+            description,
+            ColorPair::new(LIGHT_GRAY, BLACK),
+        );
+    }
+
+    draw_batch.print_color_centered(
+        11 + INVENTORY_CAPACITY as i32,
+        "Up and down to select, Enter to use, E to equip or remove, D to drop, Escape to close.",
+        ColorPair::new(YELLOW, BLACK),
+    );
+    draw_batch.submit(10000).expect("Batch error");
+}
@@ -0,0 +1,39 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(Point)]
+#[read_component(LightSource)]
+#[read_component(Darkvision)]
+#[read_component(Player)]
+#[write_component(FieldOfView)]
+pub fn lighting(
+    ecs: &mut SubWorld,
+    #[resource] light_map: &mut LightMap,
+    #[resource] map: &mut Map,
+) {
+    light_map.clear();
+    <(&Point, &LightSource, &FieldOfView)>::query()
+        .iter(ecs)
+        .for_each(|(pos, light, fov)| light_map.add_light(*pos, light, &fov.line_of_sight));
+
+    // Entities with darkvision only see what is lit, or close enough to see in the dark
+    <(&Point, &Darkvision, &mut FieldOfView, Option<&Player>)>::query()
+        .iter_mut(ecs)
+        .for_each(|(pos, darkvision, fov, player)| {
+            fov.visible_tiles = fov
+                .line_of_sight
+                .iter()
+                .filter(|tile| {
+                    light_map.is_lit(*tile)
+                        || DistanceAlg::Pythagoras.distance2d(*pos, *tile) <= darkvision.0 as f32
+                })
+                .collect();
+
+            // Tiles only count as explored once the player has actually seen them
+            if player.is_some() {
+                fov.visible_tiles.iter().for_each(|tile| {
+                    map.revealed_tiles[map_idx(tile.x, tile.y)] = true;
+                });
+            }
+        });
+}
@@ -1,6 +1,10 @@
 use crate::prelude::*;
 
+const DARKVISION_TINT: (u8, u8, u8) = GRAY;
+const REMEMBERED_TINT: (u8, u8, u8) = DIM_GRAY;
+
 #[system]
+#[allow(clippy::borrowed_box)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
 pub fn map_render(
@@ -8,6 +12,7 @@ pub fn map_render(
     #[resource] map: &Map,
     #[resource] camera: &Camera,
     #[resource] theme: &Box<dyn MapTheme>,
+    #[resource] light_map: &LightMap,
 ) {
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
     let player_fov = fov.iter(ecs).next().unwrap();
@@ -23,10 +28,18 @@ pub fn map_render(
             let idx = map_idx(x, y);
             if map.in_bounds(pt) && player_fov.visible_tiles.contains(&pt) | map.revealed_tiles[idx]
             {
+                // Visible tiles take the color of the light falling on them, and tiles
+                // only seen with darkvision are drawn dim. Remembered tiles are darker still.
                 let tint = if player_fov.visible_tiles.contains(&pt) {
-                    WHITE
+                    let light = light_map.light_at(pt);
+                    let dark = RGB::named(DARKVISION_TINT);
+                    RGB::from_f32(
+                        light.r.max(dark.r),
+                        light.g.max(dark.g),
+                        light.b.max(dark.b),
+                    )
                 } else {
-                    DARK_GRAY
+                    RGB::named(REMEMBERED_TINT)
                 };
 
                 let glyph = theme.tile_to_render(map.tiles[idx]);
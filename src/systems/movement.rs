@@ -1,37 +1,131 @@
 use crate::prelude::*;
+use std::collections::HashSet;
 
-#[system(for_each)] // `for_each` to run the system for every matching entity
+/// Applies every `WantsToMove` of this turn
+///
+/// Moves are resolved one at a time in a fixed order: the player first, then
+/// everybody else by the tile they stand on. A blocking entity may only enter a tile
+/// that no other blocker occupies at that point, so when several entities want the
+/// same tile, the first one in that order gets it and the rest stay put.
+#[system]
+#[read_component(WantsToMove)]
 #[read_component(Player)]
 #[read_component(FieldOfView)]
+#[read_component(Point)]
+#[read_component(BlocksTile)]
+#[read_component(Confused)]
+#[read_component(Sneaking)]
+#[read_component(NoiseModifier)]
+#[read_component(Equipped)]
 pub fn movement(
-    entity: &Entity,
-    want_move: &WantsToMove,
     #[resource] map: &mut Map,
     #[resource] camera: &mut Camera,
-    ecs: &mut SubWorld,
+    #[resource] index: &mut SpatialIndex,
+    ecs: &SubWorld,
     commands: &mut CommandBuffer,
 ) {
-    if map.can_enter_tile(want_move.destination) {
+    let mut moves: Vec<(Entity, WantsToMove)> = <(Entity, &WantsToMove)>::query()
+        .iter(ecs)
+        .map(|(message, want_move)| (*message, *want_move))
+        .collect();
+
+    // Confused entities stumble off in a random direction, unless they are teleported
+    let mut rng = RandomNumberGenerator::new();
+    moves.iter_mut().for_each(|(_, want_move)| {
+        if want_move.teleport {
+            return;
+        }
+        let Ok(entry) = ecs.entry_ref(want_move.entity) else {
+            return;
+        };
+        if let (Ok(_), Ok(pos)) = (
+            entry.get_component::<Confused>(),
+            entry.get_component::<Point>(),
+        ) {
+            want_move.destination = *pos
+                + match rng.range(0, 4) {
+                    0 => Point::new(-1, 0),
+                    1 => Point::new(1, 0),
+                    2 => Point::new(0, -1),
+                    _ => Point::new(0, 1),
+                };
+        }
+    });
+
+    moves.sort_by_key(|(_, want_move)| {
+        ecs.entry_ref(want_move.entity)
+            .map(|entry| {
+                let is_player = entry.get_component::<Player>().is_ok();
+                let tile = entry
+                    .get_component::<Point>()
+                    .map(|pos| map_idx(pos.x, pos.y))
+                    .unwrap_or(usize::MAX);
+                (!is_player, tile)
+            })
+            .unwrap_or((true, usize::MAX))
+    });
+
+    // Each entity gets a single move per turn
+    let mut moved = HashSet::new();
+
+    moves.iter().for_each(|(message, want_move)| {
+        // Remove the `WantsToMove` message after processing
+        commands.remove(*message);
+
+        if !moved.insert(want_move.entity) || !map.can_enter_tile(want_move.destination) {
+            return;
+        }
+
+        // The entity may have been slain earlier this turn
+        let Ok(entry) = ecs.entry_ref(want_move.entity) else {
+            return;
+        };
+
+        if entry.get_component::<BlocksTile>().is_ok()
+            && index
+                .blocker_at(want_move.destination)
+                .is_some_and(|blocker| blocker != want_move.entity)
+        {
+            return;
+        }
+
         // Mark as `wants to move` for every entity that wants to move
         commands.add_component(want_move.entity, want_move.destination);
+        if !want_move.teleport {
+            commands.push((
+                (),
+                Noise {
+                    source: want_move.entity,
+                    pos: want_move.destination,
+                    volume: walking_noise(ecs, want_move.entity),
+                },
+            ));
+        }
+
+        // Keep the spatial index in sync, so later moves this turn see the new position
+        if let Ok(pos) = entry.get_component::<Point>() {
+            index.move_entity(want_move.entity, *pos, want_move.destination);
+        }
 
         // If that entity is the player, update the camera as well
-        if let Ok(entry) = ecs.entry_ref(want_move.entity) {
-            if let Ok(fov) = entry.get_component::<FieldOfView>() {
-                commands.add_component(want_move.entity, fov.clone_dirty());
-
-                if entry.get_component::<Player>().is_ok() {
-                    camera.on_player_move(want_move.destination);
-                    fov.visible_tiles.iter().for_each(|tile| {
-                        map.revealed_tiles[map_idx(tile.x, tile.y)] = true;
-                    });
-                }
-            }
+        if let Ok(fov) = entry.get_component::<FieldOfView>() {
+            commands.add_component(want_move.entity, fov.clone_dirty());
+
             if entry.get_component::<Player>().is_ok() {
-                camera.on_player_move(want_move.destination);
+                fov.visible_tiles.iter().for_each(|tile| {
+                    map.revealed_tiles[map_idx(tile.x, tile.y)] = true;
+                });
             }
         }
-    }
+        if entry.get_component::<Player>().is_ok() {
+            camera.on_player_move(want_move.destination);
+        }
+    });
 
-    commands.remove(*entity); // Remove the `WantsToMove` component after processing
+    // Two blockers on one tile means the rules above have been broken
+    debug_assert!(
+        index.shared_blocker_tiles().is_empty(),
+        "Blocking entities share tiles: {:?}",
+        index.shared_blocker_tiles()
+    );
 }
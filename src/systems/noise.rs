@@ -0,0 +1,86 @@
+use crate::prelude::*;
+
+/// How many turns a monster spends looking for the source of a noise
+const INVESTIGATE_PATIENCE: i32 = 5;
+
+/// Spreads every `Noise` of this turn through the map, waking sleeping monsters and
+/// sending awake ones to look for its source
+///
+/// Noise travels around walls rather than through them, losing a point of volume with
+/// every tile. Monsters ignore noises their friends make, and awake ones shrug off the
+/// quieter noises of other monsters, such as their footsteps.
+#[system]
+#[read_component(Noise)]
+#[read_component(Player)]
+#[read_component(Point)]
+#[read_component(Ai)]
+#[read_component(Asleep)]
+#[read_component(LastSeen)]
+#[read_component(Faction)]
+pub fn noise(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] factions: &FactionTable,
+) {
+    let noises: Vec<Noise> = <(Entity, &Noise)>::query()
+        .iter(ecs)
+        .map(|(message, noise)| {
+            commands.remove(*message);
+            *noise
+        })
+        .filter(|noise| noise.volume > 0)
+        .collect();
+
+    let mut woken = Vec::new();
+    noises.iter().for_each(|noise| {
+        let from_player = ecs
+            .entry_ref(noise.source)
+            .is_ok_and(|entry| entry.get_component::<Player>().is_ok());
+        let spread = DijkstraMap::new(
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            &[map.point2d_to_index(noise.pos)],
+            map,
+            noise.volume as f32,
+        );
+
+        <(Entity, &Point, &Ai, Option<&Asleep>, Option<&LastSeen>)>::query()
+            .iter(ecs)
+            .filter(|(listener, ..)| {
+                **listener != noise.source
+                    && factions.reaction_between(ecs, **listener, noise.source)
+                        != Reaction::Friendly
+            })
+            .for_each(|(listener, pos, ai, asleep, last_seen)| {
+                let heard = noise_at_distance(noise.volume, spread.map[map.point2d_to_index(*pos)]);
+                if heard < 1 {
+                    return;
+                }
+
+                if asleep.is_some() && !woken.contains(listener) {
+                    if heard >= WAKE_THRESHOLD {
+                        commands.remove_component::<Asleep>(*listener);
+                        woken.push(*listener);
+                    }
+                    return;
+                }
+                if !from_player && heard < WAKE_THRESHOLD {
+                    return;
+                }
+
+                // Guards and ambushers hold their ground, and hunters keep to their trail
+                let holds_position = matches!(ai, Ai::Guard { .. } | Ai::Ambush { .. });
+                if !holds_position && last_seen.is_none() {
+                    commands.add_component(
+                        *listener,
+                        LastSeen {
+                            pos: noise.pos,
+                            patience: INVESTIGATE_PATIENCE,
+                            arrived: false,
+                        },
+                    );
+                }
+            });
+    });
+}
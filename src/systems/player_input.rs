@@ -3,20 +3,132 @@ use crate::prelude::*;
 #[system]
 #[read_component(Point)]
 #[read_component(Player)]
-#[read_component(Enemy)]
 #[write_component(Health)]
 #[read_component(Item)]
 #[read_component(Carried)]
-#[write_component(Weapon)]
+#[read_component(Name)]
+#[read_component(Charges)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(Asleep)]
+#[read_component(Ranged)]
+#[read_component(FieldOfView)]
+#[read_component(Faction)]
+#[read_component(Sneaking)]
+#[read_component(Energy)]
+#[read_component(Resting)]
+#[read_component(Hunger)]
+#[read_component(Gold)]
+#[read_component(Shopkeeper)]
+#[allow(clippy::too_many_arguments)]
 pub fn player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
     #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] mouse_pos: &Point,
     #[resource] turn_state: &mut TurnState,
+    #[resource] targeting: &mut Targeting,
+    #[resource] inventory_menu: &mut InventoryMenu,
+    #[resource] shop_menu: &mut ShopMenu,
+    #[resource] index: &SpatialIndex,
+    #[resource] factions: &FactionTable,
 ) {
     let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
 
+    // Resting keeps waiting until the player is healed, or disturbed by getting hurt or
+    // too hungry to heal, seeing a hostile, or pressing a key
+    let resting = <(Entity, &Health, &Resting, Option<&Hunger>)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(player, health, resting, hunger)| (*player, *health, *resting, hunger.copied()))
+        .next();
+    if let Some((player, health, resting, hunger)) = resting {
+        let disturbed = key.is_some()
+            || health.current >= health.max
+            || health.current < resting.health
+            || hunger.is_some_and(|hunger| hunger.state().stops_healing())
+            || hostile_in_view(ecs, index, factions);
+        if disturbed {
+            commands.remove_component::<Resting>(player);
+        } else {
+            commands.add_component(
+                player,
+                Resting {
+                    health: health.current,
+                },
+            );
+            spend_energy(ecs, commands, player, WAIT_COST);
+            *turn_state = TurnState::PlayerTurn;
+        }
+        return;
+    }
+
     if let Some(key) = key {
+        // A sleeping player can only let the turn pass
+        let asleep = <&Asleep>::query()
+            .filter(component::<Player>())
+            .iter(ecs)
+            .next()
+            .is_some();
+        if asleep {
+            if let Some(player) = <Entity>::query()
+                .filter(component::<Player>())
+                .iter(ecs)
+                .next()
+            {
+                spend_energy(ecs, commands, *player, WAIT_COST);
+            }
+            *turn_state = TurnState::PlayerTurn;
+            return;
+        }
+
+        // Switching to sneaking and back is free
+        if *key == VirtualKeyCode::C {
+            if let Some((player, sneaking)) = <(Entity, Option<&Sneaking>)>::query()
+                .filter(component::<Player>())
+                .iter(ecs)
+                .next()
+            {
+                if sneaking.is_some() {
+                    commands.remove_component::<Sneaking>(*player);
+                } else {
+                    commands.add_component(*player, Sneaking);
+                }
+            }
+            return;
+        }
+
+        if *key == VirtualKeyCode::I {
+            *inventory_menu = InventoryMenu::new();
+            *turn_state = TurnState::Inventory;
+            return;
+        }
+
+        // Resting starts on the next frame, so that it can be interrupted like any other
+        if *key == VirtualKeyCode::R {
+            if let Some((player, health)) = <(Entity, &Health)>::query()
+                .filter(component::<Player>())
+                .iter(ecs)
+                .next()
+            {
+                commands.add_component(
+                    *player,
+                    Resting {
+                        health: health.current,
+                    },
+                );
+            }
+            return;
+        }
+
+        // Firing and throwing need a target before they take up the turn
+        if let Some((weapon, range)) = ranged_weapon(*key, ecs) {
+            let cursor = nearest_target(ecs, index, factions, range);
+            targeting.start(weapon, range, cursor, *mouse_pos);
+            *turn_state = TurnState::Targeting;
+            return;
+        }
+
         let delta = match key {
             VirtualKeyCode::Left | VirtualKeyCode::A => Point::new(-1, 0),
             VirtualKeyCode::Right | VirtualKeyCode::D => Point::new(1, 0),
@@ -30,39 +142,69 @@ pub fn player_input(
                     .next()
                     .unwrap();
 
-                let mut items = <(Entity, &Item, &Point)>::query();
+                // Gold goes into the purse instead of the inventory
+                let found: i32 = index
+                    .items_at(player_pos)
+                    .filter_map(|item_entity| {
+                        let gold = ecs
+                            .entry_ref(item_entity)
+                            .ok()
+                            .and_then(|e| e.get_component::<Gold>().ok().copied())?;
+                        commands.remove(item_entity);
+                        Some(gold.0)
+                    })
+                    .sum();
+                if found > 0 {
+                    add_gold(ecs, commands, player_entity, found);
+                }
 
-                items
-                    .iter(ecs)
-                    .filter(|(_, _item, item_pos)| **item_pos == player_pos)
-                    .for_each(|(item_entity, _item, _)| {
-                        commands.remove_component::<Point>(*item_entity);
-                        commands.add_component(*item_entity, Carried(player_entity));
-
-                        if let Ok(e) = ecs.entry_ref(*item_entity) {
-                            // If the picked up item is a weapon, remove any other carried weapons
-                            if e.get_component::<Weapon>().is_ok() {
-                                <(Entity, &Carried, &Weapon)>::query()
-                                    .iter(ecs)
-                                    .filter(|(_, carried, _)| carried.0 == player_entity)
-                                    .for_each(|(other_weapon, _, _)| {
-                                        commands.remove(*other_weapon);
-                                    });
+                // Only what fits in the inventory is picked up
+                let mut slots = inventory_slots(ecs, player_entity);
+                index
+                    .items_at(player_pos)
+                    .filter(|item_entity| {
+                        ecs.entry_ref(*item_entity)
+                            .is_ok_and(|e| e.get_component::<Gold>().is_err())
+                    })
+                    .filter(|item_entity| add_to_inventory(ecs, &mut slots, *item_entity))
+                    .for_each(|item_entity| {
+                        commands.remove_component::<Point>(item_entity);
+                        commands.add_component(item_entity, Carried(player_entity));
+
+                        // Equip the item right away if nothing is in its slot yet
+                        let slot = ecs
+                            .entry_ref(item_entity)
+                            .ok()
+                            .and_then(|e| e.get_component::<Equippable>().ok().map(|e| e.0));
+                        if let Some(slot) = slot {
+                            let slot_taken = <&Equipped>::query()
+                                .iter(ecs)
+                                .any(|e| e.owner == player_entity && e.slot == slot);
+                            if !slot_taken {
+                                commands.push((
+                                    (),
+                                    EquipItem {
+                                        owner: player_entity,
+                                        item: item_entity,
+                                    },
+                                ));
                             }
                         }
                     });
 
                 Point::zero()
             }
-            VirtualKeyCode::Key1 => use_item(0, ecs, commands),
-            VirtualKeyCode::Key2 => use_item(1, ecs, commands),
-            VirtualKeyCode::Key3 => use_item(2, ecs, commands),
-            VirtualKeyCode::Key4 => use_item(3, ecs, commands),
-            VirtualKeyCode::Key5 => use_item(4, ecs, commands),
-            VirtualKeyCode::Key6 => use_item(5, ecs, commands),
-            VirtualKeyCode::Key7 => use_item(6, ecs, commands),
-            VirtualKeyCode::Key8 => use_item(7, ecs, commands),
-            _ => Point::zero(),
+            VirtualKeyCode::Key1 => use_slot(0, ecs, commands),
+            VirtualKeyCode::Key2 => use_slot(1, ecs, commands),
+            VirtualKeyCode::Key3 => use_slot(2, ecs, commands),
+            VirtualKeyCode::Key4 => use_slot(3, ecs, commands),
+            VirtualKeyCode::Key5 => use_slot(4, ecs, commands),
+            VirtualKeyCode::Key6 => use_slot(5, ecs, commands),
+            VirtualKeyCode::Key7 => use_slot(6, ecs, commands),
+            VirtualKeyCode::Key8 => use_slot(7, ecs, commands),
+            // Wait a turn
+            VirtualKeyCode::Space | VirtualKeyCode::Period => Point::zero(),
+            _ => return,
         };
 
         let (player_entity, destination) = players
@@ -71,79 +213,144 @@ pub fn player_input(
             .next()
             .unwrap();
 
-        let mut enemies = <(Entity, &Point)>::query().filter(component::<Enemy>());
+        // Picking up and using items is quicker than a full action
+        let mut cost = match key {
+            VirtualKeyCode::G
+            | VirtualKeyCode::Key1
+            | VirtualKeyCode::Key2
+            | VirtualKeyCode::Key3
+            | VirtualKeyCode::Key4
+            | VirtualKeyCode::Key5
+            | VirtualKeyCode::Key6
+            | VirtualKeyCode::Key7
+            | VirtualKeyCode::Key8 => ITEM_COST,
+            _ => WAIT_COST,
+        };
 
         if delta.x != 0 || delta.y != 0 {
+            // Walking into a shopkeeper who is not hostile trades instead, which is free
+            let shopkeeper = index.entities_at(destination).iter().find(|o| {
+                ecs.entry_ref(o.entity)
+                    .is_ok_and(|e| e.get_component::<Shopkeeper>().is_ok())
+                    && factions.reaction_between(ecs, player_entity, o.entity) != Reaction::Hostile
+            });
+            if let Some(shopkeeper) = shopkeeper {
+                *shop_menu = ShopMenu::open(shopkeeper.entity);
+                *turn_state = TurnState::Shop;
+                return;
+            }
+
             let mut hit_something = false;
 
-            // Try to attack an enemy at the destination
-            enemies
-                .iter(ecs)
-                .filter(|(_, pos)| **pos == destination)
-                .for_each(|(entity, _)| {
+            // Try to attack anything at the destination that is not on our side
+            index
+                .entities_at(destination)
+                .iter()
+                .filter(|o| {
+                    ecs.entry_ref(o.entity)
+                        .is_ok_and(|e| e.get_component::<Health>().is_ok())
+                        && factions.reaction_between(ecs, player_entity, o.entity)
+                            != Reaction::Friendly
+                })
+                .for_each(|o| {
                     hit_something = true;
                     commands.push((
                         (),
                         WantsToAttack {
                             attacker: player_entity,
-                            victim: *entity,
+                            victim: o.entity,
+                            stats: None,
                         },
                     ));
                 });
 
             // If no enemy was hit, try to move
-            if !hit_something {
+            if hit_something {
+                cost = ATTACK_COST;
+            } else {
                 commands.push((
                     (),
                     WantsToMove {
                         entity: player_entity,
                         destination,
+                        teleport: false,
                     },
                 ));
+                cost = MOVE_COST;
             }
         }
 
-        players.iter(ecs).for_each(|(entity, pos)| {
-            let destination = *pos + delta;
-            commands.push((
-                (),
-                WantsToMove {
-                    entity: *entity,
-                    destination,
-                },
-            ));
-        });
-
+        spend_energy(ecs, commands, player_entity, cost);
         *turn_state = TurnState::PlayerTurn;
     }
 }
 
-/// Use an item from the player's inventory by its index.
-fn use_item(inventory_index: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point {
-    let player_entity = <(Entity, &Player)>::query()
+/// Use the item in a slot of the player's inventory, as numbered on the HUD
+fn use_slot(inventory_index: usize, ecs: &SubWorld, commands: &mut CommandBuffer) -> Point {
+    let player_entity = <Entity>::query()
+        .filter(component::<Player>())
         .iter(ecs)
-        .map(|(entity, _)| *entity)
         .next()
+        .copied()
         .unwrap();
 
-    let item_entity = <(Entity, &Item, &Carried)>::query()
-        .iter(ecs)
-        .filter(|(_, _, carried)| carried.0 == player_entity)
-        .enumerate()
-        .filter(|(idx, _)| *idx == inventory_index)
-        .map(|(_, (item_entity, _, _))| *item_entity)
-        .next();
-
-    if let Some(item_entity) = item_entity {
-        // activate item to show its effect
-        commands.push((
-            (),
-            ActivateItem {
-                used_by: player_entity,
-                item: item_entity,
-            },
-        ));
+    if let Some(slot) = inventory_slots(ecs, player_entity).get(inventory_index) {
+        use_item(ecs, commands, player_entity, slot.item());
     }
 
     Point::zero()
 }
+
+/// The ranged weapon a key fires, with its range
+///
+/// `F` fires the equipped ranged weapon, like a bow. The inventory keys throw or zap
+/// ranged items that are not equipped, like throwing daggers, wands and targeted scrolls.
+fn ranged_weapon(key: VirtualKeyCode, ecs: &SubWorld) -> Option<(Entity, i32)> {
+    let player = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .copied()?;
+
+    let weapon = match key {
+        VirtualKeyCode::F => <(Entity, &Equipped, &Ranged)>::query()
+            .iter(ecs)
+            .find(|(_, equipped, _)| equipped.owner == player)
+            .map(|(weapon, ..)| *weapon)?,
+        _ => {
+            let inventory_index = match key {
+                VirtualKeyCode::Key1 => 0,
+                VirtualKeyCode::Key2 => 1,
+                VirtualKeyCode::Key3 => 2,
+                VirtualKeyCode::Key4 => 3,
+                VirtualKeyCode::Key5 => 4,
+                VirtualKeyCode::Key6 => 5,
+                VirtualKeyCode::Key7 => 6,
+                VirtualKeyCode::Key8 => 7,
+                _ => return None,
+            };
+            let item = inventory_slots(ecs, player).get(inventory_index)?.item();
+            return throw_range(ecs, item).map(|range| (item, range));
+        }
+    };
+
+    let range = ecs
+        .entry_ref(weapon)
+        .ok()?
+        .get_component::<Ranged>()
+        .ok()?
+        .range;
+    Some((weapon, range))
+}
+
+/// Whether the player can see anything hostile to them
+fn hostile_in_view(ecs: &SubWorld, index: &SpatialIndex, factions: &FactionTable) -> bool {
+    <(Entity, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .any(|(player, fov)| {
+            index.visible_entities(fov).any(|(_, o)| {
+                factions.reaction_between(ecs, *player, o.entity) == Reaction::Hostile
+            })
+        })
+}
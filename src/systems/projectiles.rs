@@ -0,0 +1,92 @@
+use crate::prelude::*;
+
+/// Resolves every `WantsToShoot` of this turn into an attack on whatever is in the way
+#[system]
+#[read_component(WantsToShoot)]
+#[read_component(Point)]
+#[read_component(Damage)]
+#[read_component(Accuracy)]
+#[read_component(Equipped)]
+#[read_component(Thrown)]
+#[read_component(Charges)]
+pub fn projectiles(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] index: &SpatialIndex,
+) {
+    <(Entity, &WantsToShoot)>::query()
+        .iter(ecs)
+        .for_each(|(message, shot)| {
+            commands.remove(*message);
+
+            let Ok(shooter) = ecs.entry_ref(shot.shooter) else {
+                return;
+            };
+            let Ok(from) = shooter.get_component::<Point>().copied() else {
+                return;
+            };
+
+            // Work out the stats now, since a used up wand is gone by the time combat runs
+            let stats = match shot.weapon {
+                Some(weapon) => AttackStats::with_weapon(ecs, shot.shooter, weapon),
+                None => AttackStats::of(ecs, shot.shooter),
+            };
+
+            let path = trace_projectile(map, index, from, shot.target);
+            let landing = path.tiles.last().copied().unwrap_or(from);
+
+            // Items without damage of their own, like scrolls, take effect where they land
+            if let Some(weapon) = shot.weapon {
+                if ecs
+                    .entry_ref(weapon)
+                    .is_ok_and(|entry| entry.get_component::<Damage>().is_err())
+                {
+                    commands.push((
+                        (),
+                        ActivateItem {
+                            used_by: shot.shooter,
+                            item: weapon,
+                            target: Some(landing),
+                        },
+                    ));
+                    return;
+                }
+            }
+
+            if let Some(victim) = path.victim {
+                commands.push((
+                    (),
+                    WantsToAttack {
+                        attacker: shot.shooter,
+                        victim,
+                        stats: Some(stats),
+                    },
+                ));
+            }
+
+            let Some(weapon) = shot.weapon else {
+                return;
+            };
+            let Ok(weapon_entry) = ecs.entry_ref(weapon) else {
+                return;
+            };
+
+            // Thrown weapons land where they stopped, ready to be picked up again
+            if weapon_entry.get_component::<Thrown>().is_ok() {
+                commands.remove_component::<Carried>(weapon);
+                if weapon_entry.get_component::<Equipped>().is_ok() {
+                    commands.remove_component::<Equipped>(weapon);
+                }
+                commands.add_component(weapon, landing);
+            }
+
+            if let Ok(charges) = weapon_entry.get_component::<Charges>() {
+                if charges.0 > 1 {
+                    commands.add_component(weapon, Charges(charges.0 - 1));
+                } else {
+                    commands.remove(weapon);
+                }
+            }
+        });
+}
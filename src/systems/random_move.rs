@@ -1,61 +0,0 @@
-use crate::prelude::*;
-
-/// This system moves entities with the MovingRandomly component in a random direction.
-/// Mostly used for monster movement.
-
-#[system]
-#[read_component(Point)]
-#[read_component(MovingRandomly)]
-#[read_component(Health)]
-#[read_component(Player)]
-pub fn random_move(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
-    let mut positions = <(Entity, &Point, &Health)>::query();
-
-    <(Entity, &Point, &MovingRandomly)>::query()
-        .iter(ecs)
-        .for_each(|(entity, pos, _)| {
-            let mut rng = RandomNumberGenerator::new();
-            let delta = match rng.range(0, 4) {
-                0 => Point::new(-1, 0),
-                1 => Point::new(1, 0),
-                2 => Point::new(0, -1),
-                _ => Point::new(0, 1),
-            };
-            let destination = *pos + delta;
-
-            let mut attacked = false;
-
-            // Check if there's a player at the destination to attack
-            positions
-                .iter(ecs)
-                .filter(|(_, target_pos, _)| **target_pos == destination)
-                .for_each(|(victim, _, _)| {
-                    if ecs
-                        .entry_ref(*victim)
-                        .unwrap()
-                        .get_component::<Player>()
-                        .is_ok()
-                    {
-                        commands.push((
-                            (),
-                            WantsToAttack {
-                                attacker: *entity,
-                                victim: *victim,
-                            },
-                        ));
-                    }
-
-                    attacked = true;
-                });
-
-            if !attacked {
-                commands.push((
-                    (),
-                    WantsToMove {
-                        entity: *entity,
-                        destination,
-                    },
-                ));
-            }
-        });
-}
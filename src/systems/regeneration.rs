@@ -0,0 +1,27 @@
+use crate::prelude::*;
+
+/// Heals everything that regenerates, for the turns that have passed on the `Clock`
+///
+/// Runs before `status_effects`, which marks those turns as done. A player who is
+/// weak or starving from hunger does not heal.
+#[system]
+#[read_component(Regeneration)]
+#[read_component(Hunger)]
+#[write_component(Health)]
+pub fn regeneration(ecs: &mut SubWorld, #[resource] clock: &Clock) {
+    if clock.pending_turns < 1 {
+        return;
+    }
+
+    <(&Regeneration, &mut Health, Option<&Hunger>)>::query().for_each_mut(
+        ecs,
+        |(regeneration, health, hunger)| {
+            let starving = hunger.is_some_and(|hunger| hunger.state().stops_healing());
+            if starving || health.current >= health.max {
+                return;
+            }
+            let healed = clock.pending_intervals(regeneration.0);
+            health.current = i32::min(health.max, health.current + healed);
+        },
+    );
+}
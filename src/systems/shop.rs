@@ -0,0 +1,232 @@
+use crate::prelude::*;
+
+/// Shows the shop screen, and buys or sells the selected item
+///
+/// Trading takes no time. The shopkeeper only buys what it has the gold for, and nothing
+/// the player is wearing.
+#[system]
+#[read_component(Player)]
+#[read_component(Item)]
+#[read_component(Carried)]
+#[read_component(Name)]
+#[read_component(Description)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(Charges)]
+#[read_component(Value)]
+#[read_component(Gold)]
+pub fn shop_screen(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] menu: &mut ShopMenu,
+    #[resource] identification: &Identification,
+) {
+    let Some(player) = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .copied()
+    else {
+        return;
+    };
+    let Some(shopkeeper) = menu.shopkeeper else {
+        *turn_state = TurnState::AwaitingInput;
+        return;
+    };
+
+    let owner = if menu.selling { player } else { shopkeeper };
+    let slots = inventory_slots(ecs, owner);
+    menu.selected = usize::min(menu.selected, slots.len().saturating_sub(1));
+    let selected = slots.get(menu.selected);
+
+    if let Some(key) = key {
+        match (key, selected) {
+            (VirtualKeyCode::Escape, _) => {
+                *turn_state = TurnState::AwaitingInput;
+                return;
+            }
+            (VirtualKeyCode::Tab, _) => {
+                menu.selling = !menu.selling;
+                menu.selected = 0;
+                menu.message = None;
+            }
+            (VirtualKeyCode::Up | VirtualKeyCode::W, _) => {
+                menu.selected = menu.selected.saturating_sub(1);
+            }
+            (VirtualKeyCode::Down | VirtualKeyCode::S, _) => {
+                menu.selected = usize::min(menu.selected + 1, slots.len().saturating_sub(1));
+            }
+            (VirtualKeyCode::Return, Some(slot)) => {
+                let name = identification.name(&slot.name);
+                let message = if menu.selling {
+                    sell(ecs, commands, player, shopkeeper, slot.item(), &name)
+                } else {
+                    buy(ecs, commands, player, shopkeeper, slot.item(), &name)
+                };
+                menu.message = Some(message);
+            }
+            _ => {}
+        }
+    }
+
+    // Switching between buying and selling lists the other side's items
+    let owner = if menu.selling { player } else { shopkeeper };
+    let slots = inventory_slots(ecs, owner);
+
+    let shop_name = ecs
+        .entry_ref(shopkeeper)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<Name>()
+                .ok()
+                .map(|name| name.0.clone())
+        })
+        .unwrap_or_else(|| "Shopkeeper".to_string());
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(UI_CONSOLE_ID);
+    draw_batch.print_color_centered(
+        2,
+        if menu.selling {
+            format!("Selling to the {}", shop_name)
+        } else {
+            format!("Buying from the {}", shop_name)
+        },
+        ColorPair::new(GOLD, BLACK),
+    );
+    draw_batch.print_color_centered(
+        4,
+        format!(
+            "Your gold: {}    The {}'s gold: {}",
+            gold_of(ecs, player),
+            shop_name,
+            gold_of(ecs, shopkeeper)
+        ),
+        ColorPair::new(YELLOW, BLACK),
+    );
+
+    if slots.is_empty() {
+        draw_batch.print_centered(
+            6,
+            if menu.selling {
+                "You have nothing to sell."
+            } else {
+                "The shop has sold out."
+            },
+        );
+    }
+    slots.iter().enumerate().for_each(|(i, slot)| {
+        let color = if i == menu.selected { YELLOW } else { WHITE };
+        let cursor = if i == menu.selected { ">" } else { " " };
+        let price = if menu.selling {
+            sell_price(ecs, slot.item())
+        } else {
+            buy_price(ecs, slot.item())
+        };
+        draw_batch.print_color(
+            Point::new(10, 6 + i as i32),
+            format!("{} {}", cursor, slot.label(ecs, identification)),
+            ColorPair::new(color, BLACK),
+        );
+        draw_batch.print_color(
+            Point::new(70, 6 + i as i32),
+            price.map_or("-".to_string(), |price| format!("{:>5} gold", price)),
+            ColorPair::new(color, BLACK),
+        );
+    });
+
+    // Unidentified items do not give away what they are, though their price might
+    let description = slots.get(menu.selected).and_then(|slot| {
+        match identification.unidentified_description(&slot.name) {
+            Some(description) => Some(description.to_string()),
+            None => ecs.entry_ref(slot.item()).ok().and_then(|entry| {
+                entry
+                    .get_component::<Description>()
+                    .ok()
+                    .map(|d| d.0.clone())
+            }),
+        }
+    });
+    if let Some(description) = description {
+        draw_batch.print_color(
+            Point::new(10, 8 + INVENTORY_CAPACITY as i32),
+            description,
+            ColorPair::new(LIGHT_GRAY, BLACK),
+        );
+    }
+    if let Some(message) = &menu.message {
+        draw_batch.print_color_centered(
+            10 + INVENTORY_CAPACITY as i32,
+            message,
+            ColorPair::new(WHITE, BLACK),
+        );
+    }
+
+    draw_batch.print_color_centered(
+        12 + INVENTORY_CAPACITY as i32,
+        if menu.selling {
+            "Up and down to select, Enter to sell, Tab to buy instead, Escape to leave."
+        } else {
+            "Up and down to select, Enter to buy, Tab to sell instead, Escape to leave."
+        },
+        ColorPair::new(YELLOW, BLACK),
+    );
+    draw_batch.submit(10000).expect("Batch error");
+}
+
+/// Buy an item from the shopkeeper, if the player can pay for it and carry it
+fn buy(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    player: Entity,
+    shopkeeper: Entity,
+    item: Entity,
+    name: &str,
+) -> String {
+    let Some(price) = buy_price(ecs, item) else {
+        return format!("The {} is not for sale.", name);
+    };
+    if gold_of(ecs, player) < price {
+        return format!("You cannot afford the {}.", name);
+    }
+    let mut slots = inventory_slots(ecs, player);
+    if !add_to_inventory(ecs, &mut slots, item) {
+        return format!("You have no room for the {}.", name);
+    }
+
+    commands.add_component(item, Carried(player));
+    add_gold(ecs, commands, player, -price);
+    add_gold(ecs, commands, shopkeeper, price);
+    format!("You buy the {} for {} gold.", name, price)
+}
+
+/// Sell an item to the shopkeeper, if it deals in it and has the gold
+fn sell(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    player: Entity,
+    shopkeeper: Entity,
+    item: Entity,
+    name: &str,
+) -> String {
+    if ecs
+        .entry_ref(item)
+        .is_ok_and(|e| e.get_component::<Equipped>().is_ok())
+    {
+        return format!("You have to take off the {} first.", name);
+    }
+    let Some(price) = sell_price(ecs, item) else {
+        return format!("The shopkeeper has no use for the {}.", name);
+    };
+    if gold_of(ecs, shopkeeper) < price {
+        return format!("The shopkeeper cannot afford the {}.", name);
+    }
+
+    commands.add_component(item, Carried(shopkeeper));
+    add_gold(ecs, commands, shopkeeper, -price);
+    add_gold(ecs, commands, player, price);
+    format!("You sell the {} for {} gold.", name, price)
+}
@@ -0,0 +1,23 @@
+use crate::prelude::*;
+
+/// Rebuilds the spatial index from every entity with a position
+#[system]
+#[read_component(Point)]
+#[read_component(Item)]
+#[read_component(BlocksTile)]
+pub fn spatial_index(ecs: &SubWorld, #[resource] index: &mut SpatialIndex) {
+    index.clear();
+
+    <(Entity, &Point, Option<&Item>, Option<&BlocksTile>)>::query()
+        .iter(ecs)
+        .for_each(|(entity, pos, item, blocks)| {
+            index.insert(
+                *pos,
+                Occupant {
+                    entity: *entity,
+                    blocks: blocks.is_some(),
+                    is_item: item.is_some(),
+                },
+            );
+        });
+}
@@ -0,0 +1,78 @@
+use crate::prelude::*;
+
+/// Counts down everybody's status effects by the turns that have passed on the `Clock`
+///
+/// Effects last for turns of game time, so a hasted entity gets more actions out of
+/// an effect and a slowed one fewer.
+#[system]
+#[read_component(Player)]
+#[write_component(Health)]
+#[write_component(Poisoned)]
+#[write_component(Confused)]
+#[write_component(Asleep)]
+#[write_component(Hasted)]
+#[write_component(Slowed)]
+#[read_component(XpValue)]
+#[read_component(Gold)]
+#[read_component(Point)]
+#[read_component(Carried)]
+#[write_component(Experience)]
+pub fn status_effects(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] clock: &mut Clock,
+) {
+    let turns = clock.pending_turns;
+    if turns < 1 {
+        return;
+    }
+    clock.pending_turns = 0;
+
+    <(Entity, &mut Confused)>::query().for_each_mut(ecs, |(entity, confusion)| {
+        confusion.turns -= turns;
+        if confusion.turns < 1 {
+            commands.remove_component::<Confused>(*entity);
+        }
+    });
+
+    <(Entity, &mut Asleep)>::query().for_each_mut(ecs, |(entity, sleep)| {
+        sleep.turns -= turns;
+        if sleep.turns < 1 {
+            commands.remove_component::<Asleep>(*entity);
+        }
+    });
+
+    <(Entity, &mut Hasted)>::query().for_each_mut(ecs, |(entity, haste)| {
+        haste.turns -= turns;
+        if haste.turns < 1 {
+            commands.remove_component::<Hasted>(*entity);
+        }
+    });
+
+    <(Entity, &mut Slowed)>::query().for_each_mut(ecs, |(entity, slow)| {
+        slow.turns -= turns;
+        if slow.turns < 1 {
+            commands.remove_component::<Slowed>(*entity);
+        }
+    });
+
+    // Poison goes last, since it may kill and nothing else should touch the dead
+    let mut deaths = Vec::new();
+    <(Entity, &mut Poisoned, &mut Health, Option<&Player>)>::query().for_each_mut(
+        ecs,
+        |(entity, poison, health, player)| {
+            health.current -= poison.damage * i32::min(turns, poison.turns);
+            if health.current < 1 && player.is_none() {
+                deaths.push((*entity, poison.source));
+                return;
+            }
+            poison.turns -= turns;
+            if poison.turns < 1 {
+                commands.remove_component::<Poisoned>(*entity);
+            }
+        },
+    );
+    deaths
+        .into_iter()
+        .for_each(|(victim, poisoner)| kill(ecs, commands, victim, Some(poisoner)));
+}
@@ -0,0 +1,63 @@
+use crate::prelude::*;
+
+/// Moves the target cursor with the keyboard or mouse, and fires at the selected tile
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Energy)]
+#[allow(clippy::too_many_arguments)]
+pub fn targeting(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] mouse_pos: &Point,
+    #[resource] click: &MouseClick,
+    #[resource] camera: &Camera,
+    #[resource] targeting: &mut Targeting,
+    #[resource] turn_state: &mut TurnState,
+) {
+    let Some((player, player_pos, fov)) = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+    else {
+        return;
+    };
+
+    // The mouse only takes over the cursor when it moves
+    if *mouse_pos != targeting.last_mouse {
+        targeting.last_mouse = *mouse_pos;
+        targeting.cursor = *mouse_pos + Point::new(camera.left_x, camera.top_y);
+    }
+
+    let mut fire = click.0;
+    if let Some(key) = key {
+        match key {
+            VirtualKeyCode::Left | VirtualKeyCode::A => targeting.cursor += Point::new(-1, 0),
+            VirtualKeyCode::Right | VirtualKeyCode::D => targeting.cursor += Point::new(1, 0),
+            VirtualKeyCode::Up | VirtualKeyCode::W => targeting.cursor += Point::new(0, -1),
+            VirtualKeyCode::Down | VirtualKeyCode::S => targeting.cursor += Point::new(0, 1),
+            VirtualKeyCode::Return | VirtualKeyCode::Space | VirtualKeyCode::F => fire = true,
+            VirtualKeyCode::Escape => {
+                targeting.weapon = None;
+                *turn_state = TurnState::AwaitingInput;
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    if fire && targeting.is_valid(*player_pos, fov) {
+        commands.push((
+            (),
+            WantsToShoot {
+                shooter: *player,
+                weapon: targeting.weapon.take(),
+                target: targeting.cursor,
+            },
+        ));
+        spend_energy(ecs, commands, *player, ATTACK_COST);
+        *turn_state = TurnState::PlayerTurn;
+    }
+}
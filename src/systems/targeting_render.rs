@@ -0,0 +1,60 @@
+use crate::prelude::*;
+
+/// Highlights the tiles in range, the line a shot would take and the target cursor
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+pub fn targeting_render(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] index: &SpatialIndex,
+    #[resource] camera: &Camera,
+    #[resource] targeting: &Targeting,
+) {
+    let Some((player_pos, fov)) = <(&Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+    else {
+        return;
+    };
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(BACKGROUND_CONSOLE_ID);
+    let offset = Point::new(camera.left_x, camera.top_y);
+
+    // Only visible tiles can be targeted
+    fov.visible_tiles
+        .iter()
+        .filter(|pos| {
+            DistanceAlg::Pythagoras.distance2d(*player_pos, *pos) <= targeting.range as f32
+        })
+        .for_each(|pos| {
+            draw_batch.set_bg(pos - offset, RGB::from_u8(0, 0, 96));
+        });
+
+    trace_projectile(map, index, *player_pos, targeting.cursor)
+        .tiles
+        .iter()
+        .for_each(|pos| {
+            draw_batch.set_bg(*pos - offset, RGB::from_u8(128, 112, 0));
+        });
+
+    let cursor_color = if targeting.is_valid(*player_pos, fov) {
+        GREEN
+    } else {
+        RED
+    };
+    draw_batch.set_bg(targeting.cursor - offset, RGB::named(cursor_color));
+    draw_batch.submit(1000).expect("Batch error");
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(UI_CONSOLE_ID);
+    draw_batch.print_color_centered(
+        2,
+        "Select a target with the cursor keys or mouse. Enter to fire, Escape to cancel.",
+        ColorPair::new(YELLOW, BLACK),
+    );
+    draw_batch.submit(10200).expect("Batch error");
+}
@@ -6,8 +6,13 @@ use crate::prelude::*;
 #[read_component(Health)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn tooltips(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camera: &Camera) {
-    let mut positions = <(Entity, &Point, &Name)>::query();
+pub fn tooltips(
+    ecs: &SubWorld,
+    #[resource] mouse_pos: &Point,
+    #[resource] camera: &Camera,
+    #[resource] index: &SpatialIndex,
+    #[resource] identification: &Identification,
+) {
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
     let player_fov = fov.iter(ecs).next().unwrap();
 
@@ -17,21 +22,26 @@ pub fn tooltips(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camer
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(UI_CONSOLE_ID);
 
-    positions
-        .iter(ecs)
-        .filter(|(_, pos, _)| **pos == map_pos && player_fov.visible_tiles.contains(pos))
-        .for_each(|(entity, _, name)| {
+    if player_fov.visible_tiles.contains(&map_pos) {
+        index.entities_at(map_pos).iter().for_each(|o| {
+            let Ok(entry) = ecs.entry_ref(o.entity) else {
+                return;
+            };
+            let Ok(name) = entry.get_component::<Name>() else {
+                return;
+            };
+
             // Since the tooltip layer is 4x the resolution of the map layer (see main.rs),
             // we need to scale the position to match that.
             let screen_pos = *mouse_pos * 4;
-            let display =
-                if let Ok(health) = ecs.entry_ref(*entity).unwrap().get_component::<Health>() {
-                    format!("{} ({} HP)", name.0, health.current)
-                } else {
-                    name.0.clone()
-                };
+            let display = if let Ok(health) = entry.get_component::<Health>() {
+                format!("{} ({} HP)", name.0, health.current)
+            } else {
+                identification.name(&name.0)
+            };
             draw_batch.print(screen_pos, display);
         });
+    }
 
     draw_batch.submit(10100).expect("Batch error");
 }
@@ -5,24 +5,149 @@ use crate::prelude::*;
 #[read_component(ProvidesHealing)]
 #[write_component(Health)]
 #[read_component(ProvidesDungeonMap)]
-pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
+#[read_component(ProvidesNutrition)]
+#[write_component(Hunger)]
+#[read_component(ProvidesStatus)]
+#[read_component(ProvidesAreaDamage)]
+#[read_component(ProvidesChainLightning)]
+#[read_component(ProvidesMagicMissile)]
+#[read_component(ProvidesTeleport)]
+#[read_component(ProvidesCharm)]
+#[read_component(ProvidesIdentify)]
+#[read_component(Carried)]
+#[read_component(Name)]
+#[read_component(Faction)]
+#[read_component(Player)]
+#[read_component(Point)]
+pub fn use_items(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &mut Map,
+    #[resource] index: &SpatialIndex,
+    #[resource] factions: &FactionTable,
+    #[resource] identification: &mut Identification,
+) {
     // list of healing effects to apply
     let mut healing_to_apply = Vec::<(Entity, i32)>::new();
+    let mut food_to_eat = Vec::<(Entity, i32)>::new();
+    let mut rng = RandomNumberGenerator::new();
 
     // Process all activated items
     <(Entity, &ActivateItem)>::query()
         .iter(ecs)
         .for_each(|(entity, activate)| {
             if let Ok(entry) = ecs.entry_ref(activate.item) {
+                // The player learns what an item is by using it
+                let used_by_player = ecs
+                    .entry_ref(activate.used_by)
+                    .is_ok_and(|user| user.get_component::<Player>().is_ok());
+                if let (Ok(name), true) = (entry.get_component::<Name>(), used_by_player) {
+                    identification.identify(&name.0);
+                }
+
+                if entry.get_component::<ProvidesIdentify>().is_ok() {
+                    <(&Carried, &Name)>::query()
+                        .iter(ecs)
+                        .filter(|(carried, _)| carried.0 == activate.used_by)
+                        .for_each(|(_, name)| identification.identify(&name.0));
+                }
+
                 // Check for healing
                 if let Ok(healing) = entry.get_component::<ProvidesHealing>() {
                     healing_to_apply.push((activate.used_by, healing.amount));
                 }
 
+                if let Ok(nutrition) = entry.get_component::<ProvidesNutrition>() {
+                    food_to_eat.push((activate.used_by, nutrition.0));
+                }
+
                 // Check for dungeon map reveal
                 if entry.get_component::<ProvidesDungeonMap>().is_ok() {
                     map.revealed_tiles.iter_mut().for_each(|tile| *tile = true);
                 }
+
+                // Check for status effects, like haste
+                if let Ok(statuses) = entry.get_component::<ProvidesStatus>() {
+                    statuses.0.iter().for_each(|(status, turns)| {
+                        status.apply(commands, activate.used_by, *turns, activate.used_by);
+                    });
+                }
+
+                // Targeted effects go off where the item landed, or on the user
+                let user_pos = ecs
+                    .entry_ref(activate.used_by)
+                    .ok()
+                    .and_then(|user| user.get_component::<Point>().ok().copied());
+                if let Some(target) = activate.target.or(user_pos) {
+                    // Charmed monsters switch sides
+                    if entry.get_component::<ProvidesCharm>().is_ok() {
+                        let faction = ecs
+                            .entry_ref(activate.used_by)
+                            .ok()
+                            .and_then(|user| user.get_component::<Faction>().ok().cloned());
+                        let victim = index.blocker_at(target).filter(|victim| {
+                            ecs.entry_ref(*victim)
+                                .is_ok_and(|entry| entry.get_component::<Player>().is_err())
+                        });
+                        if let (Some(victim), Some(faction)) = (victim, faction) {
+                            commands.add_component(victim, faction);
+                        }
+                    }
+
+                    let mut hit = |victim: Entity, damage: i32| {
+                        commands.push((
+                            (),
+                            WantsToAttack {
+                                attacker: activate.used_by,
+                                victim,
+                                stats: Some(AttackStats::spell(damage)),
+                            },
+                        ));
+                    };
+
+                    if let Ok(area) = entry.get_component::<ProvidesAreaDamage>() {
+                        // The blast does not reach around walls
+                        field_of_view(target, area.radius, map)
+                            .into_iter()
+                            .filter(|pos| {
+                                DistanceAlg::Pythagoras.distance2d(target, *pos)
+                                    <= area.radius as f32
+                            })
+                            .filter_map(|pos| index.blocker_at(pos))
+                            .filter(|victim| has_health(ecs, *victim))
+                            .for_each(|victim| hit(victim, area.damage));
+                    }
+
+                    if let Ok(missile) = entry.get_component::<ProvidesMagicMissile>() {
+                        if let Some(victim) = index.blocker_at(target) {
+                            hit(victim, missile.damage);
+                        }
+                    }
+
+                    if let Ok(lightning) = entry.get_component::<ProvidesChainLightning>() {
+                        if let Some(victim) = index.blocker_at(target) {
+                            let caster = activate.used_by;
+                            chain_lightning(ecs, factions, caster, victim, target, lightning.jumps)
+                                .into_iter()
+                                .for_each(|victim| hit(victim, lightning.damage));
+                        }
+                    }
+                }
+
+                if entry.get_component::<ProvidesTeleport>().is_ok() {
+                    if let Some(destination) =
+                        user_pos.and_then(|pos| teleport_destination(map, index, pos, &mut rng))
+                    {
+                        commands.push((
+                            (),
+                            WantsToMove {
+                                entity: activate.used_by,
+                                destination,
+                                teleport: true,
+                            },
+                        ));
+                    }
+                }
             }
 
             // Remove the used item from the game
@@ -38,4 +163,94 @@ pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] m
             }
         }
     }
+
+    for (eater, nutrition) in food_to_eat {
+        if let Ok(mut entry) = ecs.entry_mut(eater) {
+            if let Ok(hunger) = entry.get_component_mut::<Hunger>() {
+                hunger.eat(nutrition);
+            }
+        }
+    }
+}
+
+/// How far chain lightning can arc from one enemy to the next
+const CHAIN_RANGE: f32 = 4.0;
+
+/// Teleporting should take the user somewhere else, not just a step away
+const MIN_TELEPORT_DISTANCE: f32 = 10.0;
+
+fn has_health(ecs: &SubWorld, entity: Entity) -> bool {
+    ecs.entry_ref(entity)
+        .is_ok_and(|entry| entry.get_component::<Health>().is_ok())
+}
+
+/// The entities struck by chain lightning, starting with the one it was aimed at
+///
+/// After the first, the lightning only arcs to entities hostile to the caster.
+fn chain_lightning(
+    ecs: &SubWorld,
+    factions: &FactionTable,
+    caster: Entity,
+    first: Entity,
+    target: Point,
+    jumps: i32,
+) -> Vec<Entity> {
+    let mut struck = vec![first];
+    let mut from = target;
+    for _ in 0..jumps {
+        let next = <(Entity, &Point)>::query()
+            .filter(component::<Health>())
+            .iter(ecs)
+            .filter(|(entity, _)| !struck.contains(entity))
+            .filter(|(entity, _)| {
+                factions.reaction_between(ecs, caster, **entity) == Reaction::Hostile
+            })
+            .map(|(entity, pos)| {
+                (
+                    *entity,
+                    *pos,
+                    DistanceAlg::Pythagoras.distance2d(from, *pos),
+                )
+            })
+            .filter(|(_, _, distance)| *distance <= CHAIN_RANGE)
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+        let Some((entity, pos, _)) = next else {
+            break;
+        };
+        struck.push(entity);
+        from = pos;
+    }
+
+    struck
+}
+
+/// A random unoccupied floor tile that can be walked to from `from`
+fn teleport_destination(
+    map: &Map,
+    index: &SpatialIndex,
+    from: Point,
+    rng: &mut RandomNumberGenerator,
+) -> Option<Point> {
+    let reachable = DijkstraMap::new(
+        SCREEN_WIDTH,
+        SCREEN_HEIGHT,
+        &[map.point2d_to_index(from)],
+        map,
+        1024.0,
+    );
+    let candidates: Vec<Point> = reachable
+        .map
+        .iter()
+        .enumerate()
+        .filter(|(_, distance)| **distance < f32::MAX)
+        .map(|(idx, _)| map.index_to_point2d(idx))
+        .filter(|pos| map.can_enter_tile(*pos) && index.blocker_at(*pos).is_none())
+        .filter(|pos| DistanceAlg::Pythagoras.distance2d(from, *pos) >= MIN_TELEPORT_DISTANCE)
+        .collect();
+
+    if candidates.is_empty() {
+        None
+    } else {
+        Some(candidates[rng.random_slice_index(&candidates)?])
+    }
 }
@@ -0,0 +1,102 @@
+use crate::prelude::*;
+
+/// Whether the left mouse button was clicked this frame
+pub struct MouseClick(pub bool);
+
+/// The target selection in progress while in `TurnState::Targeting`
+pub struct Targeting {
+    /// The item being fired or thrown
+    pub weapon: Option<Entity>,
+    pub range: i32,
+    /// The selected tile, in map coordinates
+    pub cursor: Point,
+    /// Where the mouse was last frame, so that it only takes over the cursor when moved
+    pub last_mouse: Point,
+}
+
+impl Targeting {
+    pub fn new() -> Self {
+        Self {
+            weapon: None,
+            range: 0,
+            cursor: Point::zero(),
+            last_mouse: Point::zero(),
+        }
+    }
+
+    /// Begin selecting a target for a weapon, starting at the given tile
+    pub fn start(&mut self, weapon: Entity, range: i32, cursor: Point, mouse_pos: Point) {
+        self.weapon = Some(weapon);
+        self.range = range;
+        self.cursor = cursor;
+        self.last_mouse = mouse_pos;
+    }
+
+    /// Whether the cursor is on a tile the player can shoot at
+    pub fn is_valid(&self, player_pos: Point, fov: &FieldOfView) -> bool {
+        self.cursor != player_pos
+            && fov.visible_tiles.contains(&self.cursor)
+            && DistanceAlg::Pythagoras.distance2d(player_pos, self.cursor) <= self.range as f32
+    }
+}
+
+/// The closest visible hostile within range, or the player's own tile if there is none
+pub fn nearest_target(
+    ecs: &impl EntityStore,
+    index: &SpatialIndex,
+    factions: &FactionTable,
+    range: i32,
+) -> Point {
+    let Some((player, player_pos, fov)) = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+    else {
+        return Point::zero();
+    };
+
+    index
+        .visible_entities(fov)
+        .filter(|(_, o)| factions.reaction_between(ecs, *player, o.entity) == Reaction::Hostile)
+        .map(|(pos, _)| (pos, DistanceAlg::Pythagoras.distance2d(*player_pos, pos)))
+        .filter(|(_, distance)| *distance <= range as f32)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(pos, _)| pos)
+        .unwrap_or(*player_pos)
+}
+
+/// Where a projectile flying from one tile towards another ends up
+pub struct ProjectilePath {
+    /// The tiles it passes through, ending with the one it lands on
+    pub tiles: Vec<Point>,
+    /// The first blocking entity in its way, which it hits
+    pub victim: Option<Entity>,
+}
+
+/// Trace a projectile from `from` towards `to`
+///
+/// The projectile stops in front of the first opaque tile, or on the first tile
+/// with a blocking entity, which it hits.
+pub fn trace_projectile(map: &Map, index: &SpatialIndex, from: Point, to: Point) -> ProjectilePath {
+    let mut path = ProjectilePath {
+        tiles: Vec::new(),
+        victim: None,
+    };
+
+    for pos in line2d_bresenham(from, to).into_iter().skip(1) {
+        let Some(idx) = map.try_idx(pos) else {
+            break;
+        };
+        if map.is_opaque(idx) {
+            break;
+        }
+
+        path.tiles.push(pos);
+        if let Some(blocker) = index.blocker_at(pos) {
+            path.victim = Some(blocker);
+            break;
+        }
+    }
+
+    path
+}
@@ -1,8 +1,17 @@
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TurnState {
     AwaitingInput,
+    /// Picking a tile to shoot or throw at
+    Targeting,
+    /// Looking through the inventory screen
+    Inventory,
+    /// Buying and selling with a shopkeeper
+    Shop,
     PlayerTurn,
+    /// Every monster with enough energy acts
     MonsterTurn,
+    /// Choosing a perk for the level the player just reached
+    LevelUp,
     GameOver,
     Victory,
     NextLevel,
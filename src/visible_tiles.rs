@@ -0,0 +1,63 @@
+use crate::prelude::*;
+
+const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
+const NUM_WORDS: usize = NUM_TILES.div_ceil(64);
+
+/// The set of tiles inside a field of view, stored as one bit per map tile
+///
+/// Every visible tile is probed by the renderers once per cell and frame, and the
+/// whole set is cloned whenever its owner moves. A fixed-size bitset makes both of
+/// those a handful of word operations instead of hashing every point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VisibleTiles {
+    bits: [u64; NUM_WORDS],
+}
+
+impl VisibleTiles {
+    pub fn new() -> Self {
+        Self {
+            bits: [0; NUM_WORDS],
+        }
+    }
+
+    /// Mark a tile as visible. Points outside the map are ignored.
+    pub fn insert(&mut self, pos: Point) {
+        if let Some(idx) = Self::idx(pos) {
+            self.bits[idx / 64] |= 1 << (idx % 64);
+        }
+    }
+
+    pub fn contains(&self, pos: &Point) -> bool {
+        Self::idx(*pos).is_some_and(|idx| self.bits[idx / 64] & (1 << (idx % 64)) != 0)
+    }
+
+    /// Every visible tile, in map index order
+    pub fn iter(&self) -> impl Iterator<Item = Point> + '_ {
+        self.bits
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| **word != 0)
+            .flat_map(|(word_idx, word)| {
+                (0..64)
+                    .filter(move |bit| word & (1 << bit) != 0)
+                    .map(move |bit| word_idx * 64 + bit)
+            })
+            .map(|idx| Point::new(idx as i32 % SCREEN_WIDTH, idx as i32 / SCREEN_WIDTH))
+    }
+
+    fn idx(pos: Point) -> Option<usize> {
+        if pos.x >= 0 && pos.x < SCREEN_WIDTH && pos.y >= 0 && pos.y < SCREEN_HEIGHT {
+            Some(map_idx(pos.x, pos.y))
+        } else {
+            None
+        }
+    }
+}
+
+impl FromIterator<Point> for VisibleTiles {
+    fn from_iter<I: IntoIterator<Item = Point>>(iter: I) -> Self {
+        let mut tiles = Self::new();
+        iter.into_iter().for_each(|pos| tiles.insert(pos));
+        tiles
+    }
+}